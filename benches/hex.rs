@@ -0,0 +1,28 @@
+//! Decode the hex digests of a manifest. Run with and without `--features simd-hex` to compare
+//! the scalar and the SIMD implementation.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use parser_tester::mtree::digest::{from_hex, to_hex};
+
+fn hex(c: &mut Criterion) {
+    let digests: Vec<String> = (0..10_000u32)
+        .map(|i| to_hex(&[i.to_le_bytes(); 8].concat()))
+        .collect();
+    let len: usize = digests.iter().map(String::len).sum();
+
+    let mut group = c.benchmark_group("hex");
+    group.throughput(Throughput::Bytes(len as u64));
+    group.bench_function("from_hex", |b| {
+        b.iter(|| {
+            for digest in &digests {
+                black_box(from_hex::<32>(black_box(digest)).unwrap());
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, hex);
+criterion_main!(benches);
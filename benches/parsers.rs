@@ -0,0 +1,64 @@
+//! Compare the throughput of all parser backends on manifests of different shapes.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use parser_tester::mtree::{Backend, MtreeBackend};
+
+const SMALL: &str = include_str!("../tests/corpus/keywords.mtree");
+const MEDIUM: &str = include_str!("../tests/corpus/zbar.mtree");
+
+/// A manifest of a big package, with the entries of the medium one repeated in many directories.
+fn large() -> String {
+    let mut input = String::from("#mtree\n");
+    for i in 0..200 {
+        for line in MEDIUM.lines().skip(1) {
+            match line.strip_prefix("./") {
+                Some(path) => input.push_str(&format!("./dir{i}/{path}\n")),
+                None => input.push_str(&format!("{line}\n")),
+            }
+        }
+    }
+    input
+}
+
+/// Inputs that stress single parts of the grammar: a huge path, a line with thousands of
+/// properties and thousands of `/set` lines.
+fn pathological() -> Vec<(&'static str, String)> {
+    let long_path = format!("#mtree\n./{} type=file\n", "a".repeat(1 << 20));
+    let many_properties = format!("#mtree\n./a{}\n", " uid=0 mode=644".repeat(10_000));
+    let many_sets = format!(
+        "#mtree\n{}./a type=file\n",
+        "/set type=file uid=0 gid=0 mode=644\n".repeat(10_000)
+    );
+
+    vec![
+        ("long-path", long_path),
+        ("many-properties", many_properties),
+        ("many-sets", many_sets),
+    ]
+}
+
+fn parsers(c: &mut Criterion) {
+    let mut inputs = vec![
+        ("small", SMALL.to_string()),
+        ("medium", MEDIUM.to_string()),
+        ("large", large()),
+    ];
+    inputs.extend(pathological());
+
+    for (name, input) in &inputs {
+        let input = input.trim();
+        let mut group = c.benchmark_group(*name);
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        for backend in Backend::ALL {
+            group.bench_function(backend.name(), |b| {
+                b.iter(|| backend.parse(black_box(input)).unwrap())
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, parsers);
+criterion_main!(benches);
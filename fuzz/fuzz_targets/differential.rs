@@ -0,0 +1,16 @@
+//! Check that all backends produce the same statements and accept or reject the same inputs.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use parser_tester::mtree::{Backend, MtreeBackend};
+
+fuzz_target!(|input: &str| {
+    let results: Vec<_> = Backend::ALL
+        .iter()
+        .map(|backend| (backend, backend.parse(input).ok()))
+        .collect();
+    let (reference, expected) = &results[0];
+    for (backend, actual) in &results[1..] {
+        assert_eq!(expected, actual, "{reference} and {backend} disagree");
+    }
+});
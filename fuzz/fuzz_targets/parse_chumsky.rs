@@ -0,0 +1,15 @@
+//! Feed arbitrary input into the chumsky backend and resolve the result, which must never panic.
+//!
+//! Run with a timeout to also catch inputs that take non-linear time, e.g.
+//! `cargo fuzz run parse_chumsky -- -timeout=1 -max_len=65536`.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use parser_tester::mtree::{resolve, Backend, MtreeBackend};
+
+fuzz_target!(|data: &[u8]| {
+    let input = String::from_utf8_lossy(data);
+    if let Ok(statements) = Backend::Chumsky.parse(&input) {
+        resolve(&statements);
+    }
+});
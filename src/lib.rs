@@ -0,0 +1,3 @@
+pub mod mtree;
+#[cfg(feature = "wasm")]
+pub mod wasm;
@@ -1,206 +1,2059 @@
-#![allow(dead_code)]
+#[cfg(feature = "tar")]
+use std::collections::BTreeMap;
 use std::{
-    fs::{self},
-    io::Read,
+    collections::BTreeSet,
+    env, fmt,
+    fs::{self, File},
+    hint,
+    io::{self, BufWriter, IsTerminal, Write},
+    mem,
+    path::{Path, PathBuf},
+    process::{self, ExitCode},
+    slice,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context, Result};
+use clap::{builder::TypedValueParser, ColorChoice, Parser, Subcommand, ValueEnum};
+use flate2::{write::GzEncoder, Compression};
+use ignore::WalkBuilder;
+use indicatif::{HumanBytes, ProgressBar, ProgressStyle};
+use notify::{Event, RecursiveMode, Watcher};
+use parser_tester::mtree::{
+    self,
+    export::{self, Column},
+    localdb::{self, CheckOptions},
+    sarif::{self, Artifact},
+    writer, Backend, Change, ChangeKind, CreateOptions, Diagnostic, DigestAlgorithm,
+    DuplicatePolicy, Entry, EntryFilter, Excludes, FileFormat, Limits, MtreeBackend,
+    NormalizeOptions, ParseFileError, ParseOptions, ParsedFile, Progress, Severity, Stats,
+    Strictness,
+};
+#[cfg(feature = "digests")]
+use parser_tester::mtree::{
+    verify::{verify_with_digests, DigestOptions},
+    RefreshOptions,
 };
+use serde::Serialize;
 
-use anyhow::Result;
-use ariadne::{Color, Label, Report, ReportKind, Source};
-use chumsky::{prelude::*, text::ascii, Parser};
-use flate2::read::GzDecoder;
-use text::newline;
-
-/// Each line represents a line in a .MTREE file
-#[derive(Debug, Clone)]
-enum Statement<'a> {
-    /// The initial `#mtree` line at the top of the file
-    Init,
-    /// A `/set` command followed by some properties
-    Set(Vec<DefaultProperty<'a>>),
-    /// A `/unset` command followed by some properties
-    Unset(Vec<DefaultProperty<'a>>),
-    /// Any path statement followed by some properties
-    Path {
-        path: &'a str,
-        properties: Vec<Property<'a>>,
+/// The exit code for lints, audit findings and verification mismatches.
+const EXIT_FINDINGS: u8 = 1;
+/// The exit code for manifests that don't parse or exceed the limits, and for invalid arguments.
+const EXIT_PARSE: u8 = 2;
+/// The exit code for I/O and all other errors.
+const EXIT_ERROR: u8 = 3;
+
+/// Parse, check and generate mtree(5) manifests like the `.MTREE` of ALPM packages.
+#[derive(Parser)]
+#[command(
+    version,
+    after_help = "Exit codes:\n  \
+        0  success\n  \
+        1  lints, audit findings or mismatches\n  \
+        2  a manifest doesn't parse or the arguments are invalid\n  \
+        3  I/O and other errors"
+)]
+struct Cli {
+    #[command(flatten)]
+    output: Output,
+    #[command(flatten)]
+    logging: Logging,
+    /// The number of threads for parsing multiple files and computing digests, `0` uses one
+    /// per CPU.
+    #[arg(short, long, global = true, default_value_t = 0)]
+    jobs: usize,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the resolved entries of manifests.
+    Parse {
+        /// Parse the files again whenever they change.
+        #[arg(long)]
+        watch: bool,
+        /// The format of the input, detected from the content of each file if omitted.
+        #[arg(long, value_enum)]
+        from: Option<InputFormat>,
+        /// The manifests to read, `-` reads stdin. Globs are expanded and directories are
+        /// searched for manifests recursively. URLs are downloaded with the `http` feature.
+        #[arg(default_value = "-")]
+        files: Vec<PathBuf>,
+    },
+    /// Print the entry of a single path, e.g. `./usr/bin/foo`.
+    Get {
+        /// The manifest to search, `-` reads stdin.
+        manifest: PathBuf,
+        path: String,
+    },
+    /// Check that manifests are valid and print all errors and warnings.
+    Validate {
+        /// How forgiving the parser is, `strict` turns all warnings into errors.
+        #[arg(long, default_value_t = Strictness::Default)]
+        strictness: Strictness,
+        /// The manifests to read, `-` reads stdin. Globs are expanded and directories are
+        /// searched for manifests recursively. URLs are downloaded with the `http` feature.
+        #[arg(default_value = "-")]
+        files: Vec<PathBuf>,
+    },
+    /// Check manifests for deviations from the format and security relevant entries, like
+    /// world-writable files or symlinks that escape the package.
+    Lint {
+        /// Fail if there is a finding of at least this severity.
+        #[arg(long, default_value_t = Severity::Error)]
+        fail_on: Severity,
+        /// The manifests to read, `-` reads stdin. Globs are expanded and directories are
+        /// searched for manifests recursively. URLs are downloaded with the `http` feature.
+        #[arg(default_value = "-")]
+        files: Vec<PathBuf>,
+    },
+    /// Convert entries between formats, e.g. a manifest to JSON or JSON back to a manifest.
+    Convert {
+        /// The format of the input, detected from the content if omitted.
+        #[arg(long, value_enum)]
+        from: Option<InputFormat>,
+        /// The format to convert to.
+        #[arg(long, value_enum)]
+        to: ConvertFormat,
+        /// The file to convert, `-` reads stdin.
+        #[arg(default_value = "-")]
+        file: PathBuf,
+        #[command(flatten)]
+        normalize: NormalizeArgs,
+    },
+    /// Check a manifest against the files on disk.
+    Verify {
+        /// The manifest to check, `-` reads stdin, or a URL with the `http` feature.
+        #[arg(default_value = "-")]
+        manifest: PathBuf,
+        /// The directory that the paths of the manifest are relative to.
+        #[arg(long, default_value = "/")]
+        root: PathBuf,
+        /// Also recompute the digests of files, by default with every algorithm that the
+        /// manifest records, or only with the given ones, e.g. `--digests=md5,sha256`.
+        #[cfg(feature = "digests")]
+        #[arg(long, value_delimiter = ',', num_args = 0.., require_equals = true, value_name = "ALGORITHMS")]
+        digests: Option<Vec<DigestAlgorithm>>,
+        /// Gitignore-style patterns of paths that aren't verified, e.g. `usr/share/doc`.
+        #[arg(long)]
+        exclude: Vec<String>,
+        /// Only verify the paths that match these globs, e.g. `/usr/bin/sudo` or
+        /// `/usr/lib/*.so*`, and skip the rest of the manifest. A `*` doesn't cross directory
+        /// boundaries, use `**` for that.
+        #[arg(long = "path", value_name = "GLOB")]
+        paths: Vec<String>,
+        /// Verify again whenever the manifest or one of its paths changes.
+        #[arg(long)]
+        watch: bool,
+    },
+    /// Generate a manifest for a directory.
+    Create(CreateArgs),
+    /// Recompute the sizes and digests of the files of a manifest from the files on disk and
+    /// print the updated manifest, e.g. after stripping the binaries of a package.
+    #[cfg(feature = "digests")]
+    Refresh {
+        /// The manifest to refresh, `-` reads stdin, or a URL with the `http` feature.
+        #[arg(default_value = "-")]
+        manifest: PathBuf,
+        /// The directory that the paths of the manifest are relative to.
+        #[arg(long, default_value = "/")]
+        root: PathBuf,
+        /// The digests to record for every file, e.g. `--digests=md5,sha256`. By default, each
+        /// file keeps the digests that it records.
+        #[arg(long, value_delimiter = ',', value_name = "ALGORITHMS")]
+        digests: Vec<DigestAlgorithm>,
+        /// Gitignore-style patterns of paths that are left as they are.
+        #[arg(long)]
+        exclude: Vec<String>,
+    },
+    /// Show the paths that differ between two manifests.
+    /// Either of them may be `-` to read it from stdin.
+    Diff {
+        old: PathBuf,
+        new: PathBuf,
+        #[command(flatten)]
+        normalize: NormalizeArgs,
+    },
+    /// Check the installed files of every package of the pacman database against their
+    /// manifests, like `pacman -Qkk`.
+    Check {
+        /// The pacman database, with the packages in its `local` directory.
+        #[arg(long, default_value = "/var/lib/pacman")]
+        dbpath: PathBuf,
+        /// The directory that the packages are installed to.
+        #[arg(long, default_value = "/")]
+        root: PathBuf,
+    },
+    /// Print a summary of the entries of manifests.
+    Stats {
+        /// The manifests to read, `-` reads stdin. Globs are expanded and directories are
+        /// searched for manifests recursively. URLs are downloaded with the `http` feature.
+        #[arg(default_value = "-")]
+        files: Vec<PathBuf>,
     },
+    /// Time every parser backend on a manifest, e.g. to reproduce a performance difference.
+    Bench {
+        /// The manifest to parse, `-` reads stdin.
+        file: PathBuf,
+        /// How often each backend parses the manifest.
+        #[arg(short = 'n', long, default_value_t = 10, value_parser = clap::value_parser!(u32).range(1..))]
+        iterations: u32,
+    },
+    /// Check the detached OpenPGP signature of a manifest or database and print the signer.
+    /// Fails if no key of the keyring made the signature over the file.
+    #[cfg(feature = "signatures")]
+    VerifySignature {
+        file: PathBuf,
+        /// The signature, `<FILE>.sig` by default.
+        #[arg(long)]
+        signature: Option<PathBuf>,
+        /// The public keys to trust, binary or ASCII-armored.
+        #[arg(long)]
+        keyring: PathBuf,
+    },
+    /// Print the `.PKGINFO` of a package, e.g. a `.pkg.tar.zst`.
+    ///
+    /// With `--format spdx` or `--format cyclonedx`, print an SBOM of the package instead, and
+    /// with `--format pacman` its files like `pacman -Ql`.
+    #[cfg(feature = "tar")]
+    Pkginfo {
+        /// The package, or a URL with the `http` feature.
+        package: PathBuf,
+        /// Also print the `.BUILDINFO`.
+        #[arg(long)]
+        buildinfo: bool,
+        /// Also print a summary of the `.MTREE`.
+        #[arg(long)]
+        mtree: bool,
+    },
+    /// Compare two package versions like vercmp(8) and print `-1`, `0` or `1`, e.g. `-1` if the
+    /// first one is older.
+    #[cfg(feature = "alpm-types")]
+    Vercmp {
+        /// The versions, like `1:2.0-1`, the epoch and the release are optional.
+        #[arg(
+            num_args = 2,
+            value_names = ["A", "B"],
+            required_unless_present = "sort",
+            conflicts_with = "sort"
+        )]
+        versions: Vec<String>,
+        /// Read one version per line from stdin and print them sorted, the oldest first.
+        #[arg(long)]
+        sort: bool,
+    },
+}
+
+/// How results are printed.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// Human-readable text, or the debug representation of entries.
+    Debug,
+    Json,
+    Yaml,
+    /// Only for entries.
+    Csv,
+    /// Only for entries, a manifest without `/set` defaults.
+    Mtree,
+    /// Only for lints, a SARIF log for code scanning.
+    Sarif,
+    /// Only for packages, an SPDX SBOM with the metadata and the files.
+    Spdx,
+    /// Only for packages, a CycloneDX SBOM with the metadata and the files.
+    Cyclonedx,
+    /// The `<pkgname> <path>` lines of `pacman -Ql`, see `--pkgname`.
+    Pacman,
+}
+
+/// The formats that entries can be read from.
+#[derive(Clone, Copy, ValueEnum)]
+enum InputFormat {
+    Mtree,
+    /// As written by `--to json`.
+    Json,
+    /// As written by `--to yaml`.
+    Yaml,
+}
+
+impl InputFormat {
+    /// Detect the format from the content of a file, or from its extension if the content
+    /// doesn't tell. Formats without entries, like a `.PKGINFO`, are rejected.
+    fn detect(path: &Path, format: Option<FileFormat>) -> Result<Self> {
+        match format {
+            Some(FileFormat::Mtree) => Ok(Self::Mtree),
+            Some(FileFormat::Json) => Ok(Self::Json),
+            Some(FileFormat::Yaml) => Ok(Self::Yaml),
+            Some(format) => bail!(Failure::Parse(format!(
+                "{} looks like the {format} format, which has no entries",
+                name(path)
+            ))),
+            None => Ok(Self::from_extension(path)),
+        }
+    }
+
+    /// Detect the format from the extension of a file, anything unknown is a manifest.
+    fn from_extension(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => Self::Json,
+            Some("yaml" | "yml") => Self::Yaml,
+            _ => Self::Mtree,
+        }
+    }
+}
+
+/// The formats a manifest can be converted to.
+#[derive(Clone, Copy, ValueEnum)]
+enum ConvertFormat {
+    Json,
+    Yaml,
+    Csv,
+    /// A manifest without `/set` defaults.
+    Mtree,
+}
+
+/// How entries, statistics, mismatches and diffs are printed.
+/// The JSON and YAML output of a version can be relied upon by scripts.
+#[derive(clap::Args)]
+struct Output {
+    /// Write the results to a file instead of stdout, compressed if it ends with `.gz`, `.zst`
+    /// or `.xz`. The file is replaced at once when the command is done, and not at all if it
+    /// fails.
+    #[arg(short, long, global = true)]
+    output: Option<PathBuf>,
+    /// The parser that reads manifests, to compare the behavior of the backends.
+    #[arg(long, global = true, default_value_t = Backend::default(), value_parser = backends())]
+    parser: Backend,
+    /// What to do with a path that shows up more than once in a manifest: fail, keep the first
+    /// line, or merge the later lines into it like mtree(8).
+    #[arg(long, global = true, default_value_t = DuplicatePolicy::default())]
+    duplicates: DuplicatePolicy,
+    /// What was written for `--output` so far.
+    #[arg(skip)]
+    buffer: Mutex<Vec<u8>>,
+    /// The output format.
+    #[arg(long, global = true, value_enum, default_value_t = Format::Debug)]
+    format: Format,
+    /// The package name for `--format pacman`, packages use their own name by default.
+    #[arg(long, global = true)]
+    pkgname: Option<String>,
+    /// The CSV columns, separated by commas.
+    #[arg(long, global = true, value_delimiter = ',', default_values_t = Column::ALL.to_vec())]
+    columns: Vec<Column>,
+    /// When to color the output, `auto` only colors terminals and respects `NO_COLOR`.
+    #[arg(long, global = true, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+    /// Don't print the results, only errors and the exit code tell how it went.
+    #[arg(short, long, global = true)]
+    quiet: bool,
+    /// Don't show progress bars for long operations.
+    #[arg(long, global = true)]
+    no_progress: bool,
+    /// How parse errors and diagnostics are printed to stderr.
+    #[arg(long, global = true, value_enum, default_value_t = ErrorFormat::Human)]
+    error_format: ErrorFormat,
+}
+
+/// What is logged to stderr about what the tool does, to diagnose slow or failing runs.
+#[derive(clap::Args)]
+struct Logging {
+    /// Log what is read, parsed and verified, `-vv` logs details and `-vvv` every file.
+    /// `RUST_LOG` takes precedence if it's set. Progress bars are hidden while logging.
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+    /// Log one JSON object per line instead of human readable lines.
+    #[arg(long, global = true)]
+    log_json: bool,
+}
+
+impl Logging {
+    /// Install the subscriber that writes the logs to stderr, `colored` as decided by `--color`.
+    fn init(&self, colored: bool) {
+        let level = match self.verbose {
+            0 => "warn",
+            1 => "info",
+            2 => "debug",
+            _ => "trace",
+        };
+        let filter = tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| {
+            tracing_subscriber::EnvFilter::new(format!("warn,parser_tester={level}"))
+        });
+        let subscriber = tracing_subscriber::fmt()
+            .with_env_filter(filter)
+            .with_writer(io::stderr)
+            .with_ansi(colored);
+        if self.log_json {
+            subscriber.json().init();
+        } else {
+            subscriber.init();
+        }
+    }
+}
+
+/// The names of all parser backends, for `--parser`.
+fn backends() -> impl TypedValueParser<Value = Backend> {
+    clap::builder::PossibleValuesParser::new(Backend::ALL.iter().map(|backend| backend.name()))
+        .map(|name| name.parse::<Backend>().expect("known backend"))
+}
+
+/// A writer that collects the results for `--output`.
+struct Buffered<'a>(&'a Mutex<Vec<u8>>);
+
+impl Write for Buffered<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .lock()
+            .map_err(|_| io::Error::other("poisoned output buffer"))?
+            .write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Compress the content as the extension of the file says, see [mtree::Compression::from_extension].
+fn compress(path: &Path, content: Vec<u8>) -> Result<Vec<u8>> {
+    match mtree::Compression::from_extension(path) {
+        None => Ok(content),
+        Some(mtree::Compression::Gzip) => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&content)?;
+            Ok(encoder.finish()?)
+        }
+        #[cfg(feature = "tar")]
+        Some(mtree::Compression::Zstd) => Ok(zstd::encode_all(content.as_slice(), 0)?),
+        #[cfg(feature = "tar")]
+        Some(mtree::Compression::Xz) => {
+            let mut encoder = xz2::write::XzEncoder::new(Vec::new(), 6);
+            encoder.write_all(&content)?;
+            Ok(encoder.finish()?)
+        }
+        #[cfg(not(feature = "tar"))]
+        Some(compression) => bail!("{compression} compression requires the `tar` feature"),
+    }
+}
+
+/// Replace a file with the content at once, by writing a temporary file next to it and
+/// renaming that. Readers see either the old or the new file, never a partial one.
+fn write_atomic(path: &Path, content: &[u8]) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let file_name = path.file_name().unwrap_or(path.as_os_str());
+    let temp = dir.join(format!(
+        ".{}.{}.tmp",
+        file_name.to_string_lossy(),
+        process::id()
+    ));
+
+    let result = File::create(&temp).and_then(|mut file| {
+        file.write_all(content)?;
+        file.sync_all()?;
+        fs::rename(&temp, path)
+    });
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+/// How parse errors and diagnostics are printed.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ErrorFormat {
+    /// Annotated excerpts of the manifest.
+    Human,
+    /// One JSON object per line, with the file, span, code, severity and message.
+    Json,
+}
+
+/// A progress bar that shows bytes per second if the total size is known and files per second
+/// otherwise.
+struct Bar {
+    bar: ProgressBar,
+    /// The total number of files.
+    files: AtomicU64,
+    /// The number of files that are done.
+    done: AtomicU64,
+    /// Whether the position is in bytes instead of files.
+    bytes: AtomicBool,
+}
+
+impl Progress for Bar {
+    fn start(&self, files: u64, bytes: u64) {
+        self.files.store(files, Ordering::Relaxed);
+        self.bytes.store(bytes > 0, Ordering::Relaxed);
+        let template = if bytes > 0 {
+            self.bar.set_length(bytes);
+            "{prefix} [{elapsed_precise}] {wide_bar} {binary_bytes}/{binary_total_bytes} \
+             ({binary_bytes_per_sec}, {msg})"
+        } else {
+            self.bar.set_length(files);
+            "{prefix} [{elapsed_precise}] {wide_bar} {pos}/{len} files ({per_sec})"
+        };
+        if let Ok(style) = ProgressStyle::with_template(template) {
+            self.bar.set_style(style);
+        }
+        self.bar.reset();
+    }
+
+    fn advance(&self, bytes: u64) {
+        let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
+        let files = self.files.load(Ordering::Relaxed);
+        if self.bytes.load(Ordering::Relaxed) {
+            self.bar.inc(bytes);
+        } else {
+            self.bar.inc(1);
+        }
+        self.bar.set_message(format!("{done}/{files} files"));
+    }
+
+    fn finish(&self) {
+        self.bar.finish_and_clear();
+    }
+}
+
+/// A diagnostic of a file, as printed with `--error-format json`. The error that ends the
+/// command has no file.
+#[derive(Serialize)]
+struct ErrorReport<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file: Option<&'a str>,
+    #[serde(flatten)]
+    diagnostic: &'a Diagnostic,
+}
+
+impl Output {
+    /// Where the results are printed, nowhere with `--quiet` unless they go to `--output`.
+    fn stdout(&self) -> Box<dyn Write + '_> {
+        if self.output.is_some() {
+            Box::new(Buffered(&self.buffer))
+        } else if self.quiet {
+            Box::new(io::sink())
+        } else {
+            Box::new(io::stdout().lock())
+        }
+    }
+
+    /// Write the results to `--output` if the command succeeded or only found problems, and
+    /// pass on the result of the command.
+    fn finish(&self, result: Result<()>) -> Result<()> {
+        let failed = result
+            .as_ref()
+            .is_err_and(|err| exit_code(err) != EXIT_FINDINGS);
+        let content = mem::take(&mut *self.buffer.lock().expect("poisoned output buffer"));
+        if let (Some(path), false) = (&self.output, failed) {
+            write_atomic(path, &compress(path, content)?)
+                .with_context(|| format!("failed to write {}", path.display()))?;
+        }
+
+        result
+    }
+
+    /// A progress bar on stderr, which is hidden if stderr isn't a terminal.
+    fn progress(&self, prefix: &'static str) -> Arc<Bar> {
+        let bar = if self.quiet || self.no_progress {
+            ProgressBar::hidden()
+        } else {
+            ProgressBar::new(0)
+        };
+
+        Arc::new(Bar {
+            bar: bar.with_prefix(prefix),
+            files: AtomicU64::new(0),
+            done: AtomicU64::new(0),
+            bytes: AtomicBool::new(false),
+        })
+    }
+
+    /// How manifests are parsed: with the selected parser and strictness, within the default
+    /// [Limits].
+    fn parse_options(&self) -> ParseOptions {
+        ParseOptions {
+            backend: self.parser,
+            ..ParseOptions::default()
+        }
+    }
+
+    /// Print a diagnostic of the file `name` with the content `source` to stderr.
+    fn report(&self, name: &str, source: &str, diagnostic: &Diagnostic) -> Result<()> {
+        match self.error_format {
+            ErrorFormat::Human => {
+                let color = self.color(io::stderr());
+                diagnostic.write_with_color(name, source, io::stderr(), color)?
+            }
+            ErrorFormat::Json => {
+                let mut stderr = io::stderr().lock();
+                serde_json::to_writer(
+                    &mut stderr,
+                    &ErrorReport {
+                        file: Some(name),
+                        diagnostic,
+                    },
+                )?;
+                writeln!(stderr)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Print a value as JSON or YAML, the other formats aren't supported for it.
+    fn serialize<T: Serialize>(&self, what: &str, value: &T) -> Result<()> {
+        let mut stdout = self.stdout();
+        match self.format {
+            Format::Json => {
+                serde_json::to_writer_pretty(&mut stdout, value)?;
+                writeln!(stdout)?;
+            }
+            Format::Yaml => serde_yaml::to_writer(stdout, value)?,
+            Format::Debug
+            | Format::Csv
+            | Format::Mtree
+            | Format::Sarif
+            | Format::Spdx
+            | Format::Cyclonedx
+            | Format::Pacman => {
+                let format = self.format.to_possible_value().context("skipped format")?;
+                bail!("{what} can't be printed as {}", format.get_name())
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether the results are colored. Results written to `--output` are only colored with
+    /// `--color always`.
+    fn color_results(&self) -> bool {
+        match self.output {
+            Some(_) => matches!(self.color, ColorChoice::Always),
+            None => self.color(io::stdout()),
+        }
+    }
+
+    /// Whether text written to `stream`, stdout or stderr, is colored.
+    fn color(&self, stream: impl IsTerminal) -> bool {
+        match self.color {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()) && stream.is_terminal()
+            }
+        }
+    }
+}
+
+#[derive(clap::Args)]
+struct CreateArgs {
+    dir: PathBuf,
+    /// Compress the manifest with gzip, like the `.MTREE` of packages. Not needed if
+    /// `--output` ends with `.gz`.
+    #[arg(long)]
+    gzip: bool,
+    /// Compress the manifest with zstd.
+    #[cfg(feature = "tar")]
+    #[arg(long, conflicts_with = "gzip")]
+    zstd: bool,
+    /// The digests to record for every file, separated by commas.
+    #[arg(long, value_delimiter = ',')]
+    digest: Vec<DigestAlgorithm>,
+    /// Gitignore-style patterns of paths to skip, e.g. `.git`.
+    #[arg(long)]
+    exclude: Vec<String>,
+    /// A file with exclude patterns in the `.gitignore` format.
+    #[arg(long)]
+    exclude_from: Option<PathBuf>,
+    /// Clamp times to `SOURCE_DATE_EPOCH` and don't record the owner.
+    #[arg(long)]
+    reproducible: bool,
+    /// Clamp times to this epoch instead of `SOURCE_DATE_EPOCH`.
+    #[arg(long)]
+    source_date_epoch: Option<i64>,
+    /// Don't record modification times.
+    #[arg(long)]
+    no_time: bool,
+    /// Don't record uid and gid.
+    #[arg(long)]
+    no_owner: bool,
+}
+
+/// How entries are normalized before they are converted or compared, e.g. to diff the
+/// manifests of two builds without the times that always differ.
+#[derive(clap::Args)]
+struct NormalizeArgs {
+    /// Remove these properties from every entry, separated by commas, e.g. `--strip=time`.
+    #[arg(long, value_delimiter = ',', value_name = "PROPERTIES")]
+    strip: Vec<Column>,
+    /// Rewrite every uid and gid to 0, and every uname and gname to `root`.
+    #[arg(long)]
+    root_owner: bool,
+    /// Only keep these digests, separated by commas, e.g. `--keep-digests=md5` to compare with
+    /// a manifest that has no SHA256 digests.
+    #[arg(long, value_delimiter = ',', value_name = "ALGORITHMS")]
+    keep_digests: Option<Vec<DigestAlgorithm>>,
+}
+
+impl NormalizeArgs {
+    fn options(self) -> NormalizeOptions {
+        NormalizeOptions {
+            strip: self.strip,
+            root_owner: self.root_owner,
+            digests: self.keep_digests,
+        }
+    }
+}
+
+/// Whether the path is `-`, which stands for stdin.
+fn is_stdin(path: &Path) -> bool {
+    path == Path::new("-")
+}
+
+/// Whether the path is an `http://` or `https://` URL.
+fn is_url(path: &Path) -> bool {
+    path.to_str()
+        .is_some_and(|path| path.starts_with("https://") || path.starts_with("http://"))
+}
+
+/// Start downloading a URL, the body is read while it arrives.
+#[cfg(feature = "http")]
+fn fetch(url: &Path) -> Result<impl io::Read> {
+    mtree::http::get(&url.to_string_lossy())
+        .with_context(|| format!("failed to download {}", url.display()))
+}
+
+#[cfg(not(feature = "http"))]
+fn fetch(url: &Path) -> Result<io::Empty> {
+    bail!(
+        "{} can't be downloaded without the `http` feature",
+        url.display()
+    )
+}
+
+/// The name of an input for messages.
+fn name(path: &Path) -> String {
+    if is_stdin(path) {
+        String::from("<stdin>")
+    } else {
+        path.display().to_string()
+    }
+}
+
+/// Read a whole manifest from a file, stdin or a URL, decompressing it if necessary.
+fn read(path: &Path, limits: Limits) -> Result<String> {
+    let content = if is_stdin(path) {
+        mtree::read(io::stdin().lock(), limits)
+    } else if is_url(path) {
+        mtree::read(fetch(path)?, limits)
+    } else {
+        mtree::read_file(path, limits)
+    };
+
+    content.with_context(|| format!("failed to read {}", name(path)))
+}
+
+/// The file names of manifests, `.MTREE` in packages and `mtree` in the local pacman database.
+const MANIFEST_NAMES: &[&str] = &[".MTREE", "mtree"];
+
+/// Expand the input arguments into the files to read, in order.
+///
+/// Directories are searched recursively for [MANIFEST_NAMES], sorted by path. Paths that don't
+/// exist are expanded as globs, for shells that don't do that themselves or quoted patterns.
+fn expand(inputs: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for input in inputs {
+        if is_stdin(input) || is_url(input) || input.is_file() {
+            files.push(input.clone());
+        } else if input.is_dir() {
+            let walk = WalkBuilder::new(input)
+                .standard_filters(false)
+                .sort_by_file_path(Path::cmp)
+                .build();
+            for entry in walk {
+                let entry = entry?;
+                let is_manifest = entry
+                    .file_name()
+                    .to_str()
+                    .is_some_and(|name| MANIFEST_NAMES.contains(&name));
+                if is_manifest && entry.file_type().is_some_and(|t| t.is_file()) {
+                    files.push(entry.into_path());
+                }
+            }
+        } else {
+            let pattern = input.to_string_lossy();
+            let matches = glob::glob(&pattern)
+                .with_context(|| format!("invalid pattern {pattern}"))?
+                .collect::<Result<Vec<_>, _>>()?;
+            if matches.is_empty() {
+                bail!("no such file: {pattern}");
+            }
+            files.extend(matches);
+        }
+    }
+
+    Ok(files)
+}
+
+/// The entries of many files.
+struct ReadEntries {
+    entries: Vec<Entry<'static>>,
+    /// The number of files that don't parse.
+    unparsable: usize,
+    /// The number of files that couldn't be read.
+    unreadable: usize,
+}
+
+impl ReadEntries {
+    /// Collect the entries of a manifest that was parsed in parallel, or report its error.
+    fn bulk(
+        &mut self,
+        output: &Output,
+        path: &Path,
+        result: Result<ParsedFile, ParseFileError>,
+    ) -> Result<()> {
+        let diagnostic = match result {
+            Ok(parsed) => {
+                report_file(output, path, &parsed.diagnostics)?;
+                self.entries.extend(parsed.entries);
+                return Ok(());
+            }
+            Err(ParseFileError::Io(e)) => {
+                output.report(&name(path), "", &unreadable(&e.into()))?;
+                self.unreadable += 1;
+                return Ok(());
+            }
+            Err(ParseFileError::Syntax(diagnostic)) => diagnostic,
+            Err(ParseFileError::Limit(limit)) => Diagnostic::from(limit),
+        };
+        report_file(output, path, slice::from_ref(&diagnostic))?;
+        self.unparsable += 1;
+
+        Ok(())
+    }
+
+    /// Fail if any of the files couldn't be read or parsed, after the entries were printed.
+    fn check(&self) -> Result<()> {
+        if self.unreadable > 0 {
+            bail!("{} files couldn't be read", self.unreadable);
+        }
+        if self.unparsable > 0 {
+            bail!(Failure::Parse(format!(
+                "{} files don't parse",
+                self.unparsable
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Report the diagnostics of a manifest that was parsed in parallel. The file is read again for
+/// the context of the diagnostics, since only its entries are kept.
+fn report_file(output: &Output, path: &Path, diagnostics: &[Diagnostic]) -> Result<()> {
+    if diagnostics.is_empty() {
+        return Ok(());
+    }
+    // A file that is too large has no context.
+    let content = mtree::read_file(path, Limits::default()).unwrap_or_default();
+    for diagnostic in diagnostics {
+        output.report(&name(path), &content, diagnostic)?;
+    }
+
+    Ok(())
+}
+
+/// The error of a file that couldn't be read, to report it like a parse error.
+fn unreadable(err: &anyhow::Error) -> Diagnostic {
+    Diagnostic::error("unreadable", format!("{err:#}"))
+}
+
+/// Parse the given files in parallel and collect the entries of all of them.
+/// Files that fail to parse are reported, but don't stop the others.
+///
+/// Without a format, the format of each file is detected from its content. Manifests are
+/// parsed in parallel, the other formats and stdin one after another.
+fn read_entries(
+    output: &Output,
+    files: &[PathBuf],
+    from: Option<InputFormat>,
+    jobs: usize,
+) -> Result<ReadEntries> {
+    let files = &expand(files)?;
+    // The format of stdin and URLs is only detected once they were read.
+    let formats = files
+        .iter()
+        .map(|path| match from {
+            _ if is_stdin(path) || is_url(path) => None,
+            Some(format) => Some(Ok(format)),
+            None => Some(
+                mtree::sniff_file(path)
+                    .with_context(|| format!("failed to read {}", name(path)))
+                    .and_then(|format| InputFormat::detect(path, format)),
+            ),
+        })
+        .collect::<Vec<_>>();
+    let paths = files
+        .iter()
+        .zip(&formats)
+        .filter(|(_, format)| matches!(format, Some(Ok(InputFormat::Mtree))))
+        .map(|(path, _)| path)
+        .collect::<Vec<_>>();
+    let mut results = mtree::parse_many_with_progress(
+        &paths,
+        jobs,
+        output.parse_options(),
+        output.duplicates,
+        &*output.progress("parsing"),
+    )?
+    .into_iter();
+
+    let mut read = ReadEntries {
+        entries: Vec::new(),
+        unparsable: 0,
+        unreadable: 0,
+    };
+    for (path, format) in files.iter().zip(formats) {
+        let loaded = match format {
+            Some(Ok(InputFormat::Mtree)) => {
+                read.bulk(
+                    output,
+                    path,
+                    results.next().context("missing parse result")?,
+                )?;
+                continue;
+            }
+            Some(Ok(format)) => load_as(output, path, Some(format)),
+            Some(Err(e)) => Err(e),
+            None => load_as(output, path, from),
+        };
+        match loaded {
+            Ok(parsed) => read.entries.extend(parsed),
+            // The parse error was reported with its context.
+            Err(e) if exit_code(&e) == EXIT_PARSE => read.unparsable += 1,
+            Err(e) => {
+                output.report(&name(path), "", &unreadable(&e))?;
+                read.unreadable += 1;
+            }
+        }
+    }
+
+    Ok(read)
+}
+
+/// Read and parse a single manifest. A parse error is printed with its context.
+fn load(output: &Output, path: &Path) -> Result<Vec<Entry<'static>>> {
+    load_as(output, path, Some(InputFormat::Mtree))
+}
+
+/// Read the entries of a single file in the given format, or in the one detected from the
+/// content. A parse error is printed with its context.
+fn load_as(
+    output: &Output,
+    path: &Path,
+    format: Option<InputFormat>,
+) -> Result<Vec<Entry<'static>>> {
+    let content = read(path, Limits::default())?;
+    let format = match format {
+        Some(format) => format,
+        None => InputFormat::detect(path, mtree::sniff(&content))?,
+    };
+    let entries = match format {
+        InputFormat::Mtree => Ok(resolve(output, &name(path), &content)?),
+        InputFormat::Json => export::read_json(&content),
+        InputFormat::Yaml => export::read_yaml(&content),
+    };
+    match entries {
+        Ok(entries) => Ok(entries.into_iter().map(Entry::into_owned).collect()),
+        Err(err) => {
+            let diagnostic = Diagnostic::error("syntax", format!("{err:#}"));
+            output.report(&name(path), &content, &diagnostic)?;
+            bail!(Failure::Parse(format!("failed to parse {}", name(path))))
+        }
+    }
+}
+
+/// Parse a manifest with the selected parser and resolve its entries with the `--duplicates`
+/// policy, within the default [Limits] like `validate`. A parse error is printed with its
+/// context, and so are the deviations that the default [Strictness] tolerates and the
+/// duplicate paths.
+fn resolve<'a>(output: &Output, name: &str, content: &'a str) -> Result<Vec<Entry<'a>>> {
+    let resolved = match output
+        .parse_options()
+        .resolve(content.trim_end(), output.duplicates)
+    {
+        Ok(resolved) => resolved,
+        Err(diagnostic) => {
+            output.report(name, content, &diagnostic)?;
+            bail!(Failure::Parse(format!("failed to parse {name}")))
+        }
+    };
+    for diagnostic in &resolved.diagnostics {
+        output.report(name, content, diagnostic)?;
+    }
+
+    Ok(resolved.entries)
+}
+
+/// Print the entries of all given files.
+fn parse(output: &Output, from: Option<InputFormat>, files: &[PathBuf], jobs: usize) -> Result<()> {
+    let read = read_entries(output, files, from, jobs)?;
+    print_entries(output, &read.entries)?;
+    read.check()
 }
 
-/// This type is used in `/set` and `/unset` commands to modify the currently active defaults.
-#[derive(Debug, Clone)]
-enum DefaultProperty<'a> {
-    Uid(usize),
-    Gid(usize),
-    Mode(&'a str),
-    Type(PathType),
-}
-
-/// This type is used in a [Path] line and defines some available properties for that path.
-#[derive(Debug, Clone)]
-enum Property<'a> {
-    Mode(&'a str),
-    Type(PathType),
-    Size(usize),
-    Link(&'a str),
-    Sha256Digest(&'a str),
-    Time(usize),
-}
-
-// What kind of type is a path.
-#[derive(Debug, Clone, Copy)]
-enum PathType {
-    Dir,
-    File,
-    Link,
-}
-
-fn main() -> Result<()> {
-    let compressed = false;
-    // Either read the compressed or already uncompressed .MTREE file at the root of this
-    // repo and return the contents.
-    let content = if compressed {
-        let gz_content = fs::read(".MTREE")?;
-        let mut decoder = GzDecoder::new(gz_content.as_slice());
-
-        let mut content = String::new();
-        decoder.read_to_string(&mut content).unwrap();
-        content
+/// Print the entry of a single path, which may be given with or without the leading `./`, or
+/// as an absolute path.
+/// With the default parser, only the `/set` and `/unset` lines and the lines of the path are
+/// parsed, with `--duplicates first-wins` only up to the first line of the path.
+fn get(output: &Output, manifest: &Path, path: &str) -> Result<()> {
+    let path = manifest_path(path);
+    let content = read(manifest, Limits::default())?;
+    let entry = if output.parser == Backend::default() {
+        match mtree::find_entry_with(&content, &path, output.duplicates) {
+            Ok(resolved) => {
+                for diagnostic in &resolved.diagnostics {
+                    output.report(&name(manifest), &content, diagnostic)?;
+                }
+                resolved.entries.into_iter().next()
+            }
+            Err(diagnostic) => {
+                output.report(&name(manifest), &content, &diagnostic)?;
+                bail!(Failure::Parse(format!(
+                    "failed to parse {}",
+                    name(manifest)
+                )));
+            }
+        }
     } else {
-        String::from_utf8_lossy(&fs::read(".MTREE.extracted")?).to_string()
+        let entries = resolve(output, &name(manifest), &content)?;
+        entries.into_iter().find(|entry| entry.path == path)
+    };
+    let Some(entry) = entry else {
+        bail!("{path} isn't in {}", name(manifest));
+    };
+
+    // A single entry is printed on its own instead of as a list, for scripts like `jq .size`.
+    match output.format {
+        Format::Debug => writeln!(output.stdout(), "{entry:#?}")?,
+        Format::Json | Format::Yaml => output.serialize("entries", &entry)?,
+        _ => print_entries(output, &[entry])?,
+    }
+    Ok(())
+}
+
+/// Print entries in the output format.
+fn print_entries(output: &Output, entries: &[Entry]) -> Result<()> {
+    let mut stdout = output.stdout();
+    match output.format {
+        Format::Debug => writeln!(stdout, "{entries:#?}")?,
+        Format::Json => export::write_json(stdout, entries)?,
+        Format::Yaml => export::write_yaml(stdout, entries)?,
+        Format::Csv => export::write_csv(stdout, entries, &output.columns)?,
+        Format::Mtree => writer::write(BufWriter::new(stdout), entries)?,
+        Format::Pacman => {
+            let pkgname = output
+                .pkgname
+                .as_deref()
+                .context("--format pacman needs a --pkgname")?;
+            export::write_file_list(stdout, pkgname, entries)?;
+        }
+        Format::Sarif | Format::Spdx | Format::Cyclonedx => {
+            let format = output
+                .format
+                .to_possible_value()
+                .context("skipped format")?;
+            bail!("entries can't be printed as {}", format.get_name())
+        }
+    }
+
+    Ok(())
+}
+
+/// Print the diagnostics of all given files and fail if any of them has an error.
+fn validate(output: &Output, strictness: Strictness, files: &[PathBuf]) -> Result<()> {
+    let options = ParseOptions {
+        strictness,
+        backend: output.parser,
+        ..ParseOptions::default()
     };
+    let files = expand(files)?;
+    let mut invalid = 0;
+    for path in &files {
+        let name = name(path);
+        let content = read(path, options.limits)?;
+        match options.parse(&content) {
+            Ok(parsed) => {
+                for diagnostic in parsed.diagnostics {
+                    output.report(&name, &content, &diagnostic)?;
+                }
+            }
+            Err(diagnostic) => {
+                output.report(&name, &content, &diagnostic)?;
+                invalid += 1;
+            }
+        }
+    }
+
+    if invalid > 0 {
+        bail!(Failure::Parse(format!(
+            "{invalid} of {} files are invalid",
+            files.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Print the lints and audit findings of all given files and fail if any of them is at least as
+/// severe as `fail_on`.
+fn lint(output: &Output, fail_on: Severity, files: &[PathBuf]) -> Result<()> {
+    let options = output.parse_options();
+    let files = expand(files)?;
+    let (mut reports, mut unparsable) = (Vec::new(), 0);
+    for path in &files {
+        let content = read(path, options.limits)?;
+        let diagnostics = match options.resolve(&content, output.duplicates) {
+            Ok(resolved) => {
+                let findings = mtree::audit(&resolved.entries)
+                    .into_iter()
+                    .map(Diagnostic::from);
+                resolved.diagnostics.into_iter().chain(findings).collect()
+            }
+            Err(diagnostic) => {
+                unparsable += 1;
+                vec![diagnostic]
+            }
+        };
+        reports.push((name(path), content, diagnostics));
+    }
+
+    let failed = reports
+        .iter()
+        .flat_map(|(_, _, diagnostics)| diagnostics)
+        .filter(|diagnostic| diagnostic.severity >= fail_on)
+        .count();
+    match output.format {
+        Format::Debug => {
+            for (name, content, diagnostics) in &reports {
+                for diagnostic in diagnostics {
+                    let color = output.color_results();
+                    diagnostic.write_with_color(name, content, output.stdout(), color)?;
+                }
+            }
+        }
+        Format::Sarif => {
+            let artifacts = reports
+                .iter()
+                .map(|(name, content, diagnostics)| Artifact {
+                    uri: name,
+                    source: content,
+                    diagnostics: diagnostics.clone(),
+                })
+                .collect::<Vec<_>>();
+            let mut stdout = output.stdout();
+            sarif::write(&mut stdout, &artifacts)?;
+            writeln!(stdout)?;
+        }
+        _ => {
+            let reports = reports
+                .iter()
+                .map(|(name, _, diagnostics)| LintReport {
+                    file: name,
+                    diagnostics,
+                })
+                .collect::<Vec<_>>();
+            output.serialize("lints", &reports)?;
+        }
+    }
+
+    if unparsable > 0 {
+        bail!(Failure::Parse(format!("{unparsable} files don't parse")));
+    }
+    if failed > 0 {
+        bail!(Failure::Findings(format!(
+            "{failed} findings are at least {fail_on}"
+        )));
+    }
+    Ok(())
+}
+
+/// The diagnostics of a single file, as printed by [lint].
+#[derive(Serialize)]
+struct LintReport<'a> {
+    file: &'a str,
+    diagnostics: &'a [Diagnostic],
+}
+
+/// Print the entries of a file in another format.
+fn convert(
+    output: &Output,
+    from: Option<InputFormat>,
+    to: ConvertFormat,
+    file: &Path,
+    normalize: &NormalizeOptions,
+) -> Result<()> {
+    let mut entries = load_as(output, file, from)?;
+    mtree::normalize(&mut entries, normalize);
+    let stdout = output.stdout();
+    match to {
+        ConvertFormat::Json => export::write_json(stdout, &entries)?,
+        ConvertFormat::Yaml => export::write_yaml(stdout, &entries)?,
+        ConvertFormat::Csv => export::write_csv(stdout, &entries, &output.columns)?,
+        ConvertFormat::Mtree => writer::write(BufWriter::new(stdout), &entries)?,
+    }
+
+    Ok(())
+}
+
+/// The options of [verify] that depend on features.
+struct VerifyOptions {
+    #[cfg(feature = "digests")]
+    digests: Option<Vec<DigestAlgorithm>>,
+    #[cfg(feature = "digests")]
+    jobs: usize,
+    exclude: Vec<String>,
+    paths: Vec<String>,
+}
+
+/// The filter for the globs of `verify --path`, `None` if all paths are verified.
+fn path_filter(paths: &[String]) -> Result<Option<EntryFilter>> {
+    if paths.is_empty() {
+        return Ok(None);
+    }
+    let mut filter = EntryFilter::new();
+    for path in paths {
+        filter = filter.glob(&manifest_path(path))?;
+    }
+    Ok(Some(filter))
+}
+
+/// A path or glob as manifests have it, with a leading `./`, whether it's given absolute or
+/// relative. The root itself is `.`.
+fn manifest_path(path: &str) -> String {
+    let path = path.trim_start_matches('/');
+    let path = path.strip_prefix("./").unwrap_or(path);
+    match path.trim_end_matches('/') {
+        "" | "." => String::from("."),
+        path => format!("./{path}"),
+    }
+}
+
+/// Print every mismatch between a manifest and the filesystem, like `paccheck --file-properties`
+/// and, with digests, `--sha256sum`.
+fn verify(output: &Output, manifest: &Path, root: &Path, options: &VerifyOptions) -> Result<()> {
+    let mut entries = load(output, manifest)?;
+    if let Some(filter) = path_filter(&options.paths)? {
+        entries.retain(|entry| filter.matches(entry));
+        tracing::info!(entries = entries.len(), "selected paths");
+    }
+    let excludes = Excludes::new(options.exclude.iter().map(String::as_str))?;
+    #[cfg(feature = "digests")]
+    let mismatches = match &options.digests {
+        Some(algorithms) => {
+            let options = DigestOptions {
+                algorithms: match algorithms.as_slice() {
+                    [] => DigestAlgorithm::ALL.to_vec(),
+                    algorithms => algorithms.to_vec(),
+                },
+                threads: options.jobs,
+                excludes: Some(excludes),
+                progress: Some(output.progress("hashing")),
+            };
+            verify_with_digests(&entries, root, &options)?
+        }
+        None => mtree::verify(excludes.filter(&entries), root),
+    };
+    #[cfg(not(feature = "digests"))]
+    let mismatches = mtree::verify(excludes.filter(&entries), root);
+    if output.format == Format::Debug {
+        let mut stdout = output.stdout();
+        for mismatch in &mismatches {
+            writeln!(stdout, "{mismatch}")?;
+        }
+    } else {
+        output.serialize("mismatches", &mismatches)?;
+    }
+
+    if !mismatches.is_empty() {
+        bail!(Failure::Findings(format!(
+            "{} mismatches",
+            mismatches.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Check the installed packages of a pacman database and print the mismatches of each one.
+fn check(output: &Output, dbpath: &Path, root: &Path, jobs: usize) -> Result<()> {
+    let mut packages = Vec::new();
+    // Packages whose `desc` can't be read are reported, but don't stop the others.
+    let mut unreadable_packages = 0;
+    for package in localdb::packages(dbpath)
+        .with_context(|| format!("failed to read the database at {}", dbpath.display()))?
+    {
+        match package {
+            Ok(package) => packages.push(package),
+            Err(err) => {
+                output.report(&name(dbpath), "", &unreadable(&err.into()))?;
+                unreadable_packages += 1;
+            }
+        }
+    }
+    let options = CheckOptions {
+        threads: jobs,
+        backend: output.parser,
+        limits: Limits::default(),
+        progress: Some(output.progress("checking")),
+    };
+    let checks = localdb::check(&packages, root, &options)?;
+
+    if output.format == Format::Debug {
+        let mut stdout = output.stdout();
+        for check in &checks {
+            let name = &check.package.name;
+            if let Some(error) = &check.error {
+                writeln!(stdout, "{name}: {error}")?;
+                continue;
+            }
+            for mismatch in &check.mismatches {
+                writeln!(stdout, "{name}: {mismatch}")?;
+            }
+            writeln!(
+                stdout,
+                "{name}: {} total files, {} altered files",
+                check.files,
+                check.altered()
+            )?;
+        }
+    } else {
+        output.serialize("package checks", &checks)?;
+    }
+
+    let altered = checks.iter().filter(|check| check.altered() > 0).count();
+    let unreadable =
+        unreadable_packages + checks.iter().filter(|check| check.error.is_some()).count();
+    if unreadable > 0 {
+        bail!(Failure::Parse(format!(
+            "{unreadable} of {} packages couldn't be read",
+            checks.len() + unreadable_packages
+        )));
+    }
+    if altered > 0 {
+        bail!(Failure::Findings(format!(
+            "{altered} of {} packages have altered files",
+            checks.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Write the manifest of a directory to a file or stdout.
+fn create(output: &Output, args: CreateArgs, jobs: usize) -> Result<()> {
+    let mut options = if args.reproducible {
+        CreateOptions::reproducible()
+    } else {
+        CreateOptions::default()
+    };
+    options.digests = args.digest;
+    options.threads = jobs;
+    options.progress = Some(output.progress("hashing"));
+    options.source_date_epoch = args.source_date_epoch.or(options.source_date_epoch);
+    options.omit_time |= args.no_time;
+    options.omit_owner |= args.no_owner;
+
+    let mut patterns = args.exclude;
+    if let Some(path) = &args.exclude_from {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        patterns.extend(content.lines().map(String::from));
+    }
+    if !patterns.is_empty() {
+        options.excludes = Some(Excludes::new(patterns.iter().map(String::as_str))?);
+    }
+
+    let dir = &args.dir;
+    let entries = mtree::create(dir, &options)
+        .with_context(|| format!("failed to walk {}", dir.display()))?;
+    let mut manifest = Vec::new();
+    writer::write(&mut manifest, &entries)?;
+    // The extension of `--output` takes care of the compression.
+    let compressed = output
+        .output
+        .as_deref()
+        .is_some_and(|path| mtree::Compression::from_extension(path).is_some());
+    if args.gzip && !compressed {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&manifest)?;
+        manifest = encoder.finish()?;
+    }
+    #[cfg(feature = "tar")]
+    if args.zstd && !compressed {
+        manifest = zstd::encode_all(manifest.as_slice(), 0)?;
+    }
+
+    let mut stdout = output.stdout();
+    stdout.write_all(&manifest)?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Write a manifest with the sizes and digests of the files below `root` to a file or stdout.
+#[cfg(feature = "digests")]
+fn refresh(
+    output: &Output,
+    manifest: &Path,
+    root: &Path,
+    digests: Vec<DigestAlgorithm>,
+    exclude: &[String],
+    jobs: usize,
+) -> Result<()> {
+    let mut entries = load(output, manifest)?;
+    let options = RefreshOptions {
+        digests,
+        threads: jobs,
+        excludes: Some(Excludes::new(exclude.iter().map(String::as_str))?),
+        progress: Some(output.progress("hashing")),
+    };
+    mtree::refresh(&mut entries, root, &options)
+        .with_context(|| format!("failed to refresh {}", manifest.display()))?;
+
+    // The extension of `--output` takes care of the compression.
+    let mut stdout = BufWriter::new(output.stdout());
+    writer::write(&mut stdout, &entries)?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Print the added, removed and modified paths of two manifests.
+///
+/// The text output is like a unified diff: added paths are green and prefixed with `+`,
+/// removed ones red with `-` and modified ones yellow with `~`, followed by their changed
+/// properties.
+fn diff(output: &Output, old: &Path, new: &Path, normalize: &NormalizeOptions) -> Result<()> {
+    let (mut old, mut new) = (load(output, old)?, load(output, new)?);
+    mtree::normalize(&mut old, normalize);
+    mtree::normalize(&mut new, normalize);
+    let changes = mtree::diff(&old, &new);
+    if output.format != Format::Debug {
+        return output.serialize("diffs", &changes);
+    }
+
+    let color = output.color_results();
+    let paint = |code: &str, line: String| {
+        if color {
+            format!("\x1b[{code}m{line}\x1b[0m")
+        } else {
+            line
+        }
+    };
+    let mut stdout = output.stdout();
+    for Change { path, kind } in changes {
+        match kind {
+            ChangeKind::Added => writeln!(stdout, "{}", paint("32", format!("+ {path}")))?,
+            ChangeKind::Removed => writeln!(stdout, "{}", paint("31", format!("- {path}")))?,
+            ChangeKind::Modified(changes) => {
+                writeln!(stdout, "{}", paint("33", format!("~ {path}")))?;
+                for change in changes {
+                    writeln!(stdout, "    {change}")?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
 
-    //println!("{}", content);
+/// Print the statistics of all given files.
+fn stats(output: &Output, files: &[PathBuf], jobs: usize) -> Result<()> {
+    let read = read_entries(output, files, None, jobs)?;
+    let stats = Stats::from_entries(&read.entries);
+    match output.format {
+        Format::Debug => write!(output.stdout(), "{stats}")?,
+        _ => output.serialize("statistics", &stats)?,
+    }
 
-    // Parse the file
-    let (ast, errs) = parser().parse(content.trim()).into_output_errors();
+    read.check()
+}
 
-    // Print out the AST
-    println!("{:#?}", ast);
+#[cfg(feature = "signatures")]
+fn verify_signature(
+    output: &Output,
+    file: &Path,
+    signature: Option<PathBuf>,
+    keyring: &Path,
+) -> Result<()> {
+    use mtree::signature::{self, Keyring, SignatureError};
 
-    // Print out any errors.
-    errs.into_iter().for_each(|e| {
-        Report::build(ReportKind::Error, (), e.span().start)
-            .with_message(e.to_string())
-            .with_label(
-                Label::new(e.span().into_range())
-                    .with_message(e.reason().to_string())
-                    .with_color(Color::Red),
-            )
-            .finish()
-            .print(Source::from(&content))
-            .unwrap()
+    let keyring = File::open(keyring)
+        .map_err(anyhow::Error::from)
+        .and_then(|reader| Ok(Keyring::read(reader)?))
+        .with_context(|| format!("failed to read {}", keyring.display()))?;
+    if keyring.is_empty() {
+        bail!("the keyring doesn't contain any usable keys");
+    }
+    let signature = signature.unwrap_or_else(|| {
+        let mut path = file.as_os_str().to_owned();
+        path.push(".sig");
+        PathBuf::from(path)
     });
+    let data = fs::read(file).with_context(|| format!("failed to read {}", file.display()))?;
+    let reader = File::open(&signature)
+        .with_context(|| format!("failed to read {}", signature.display()))?;
+
+    let signer = match signature::verify_detached(&data, reader, &keyring) {
+        Ok(signer) => signer,
+        Err(SignatureError::Invalid) => bail!(Failure::Findings(format!(
+            "{} isn't signed by a key of the keyring",
+            file.display()
+        ))),
+        Err(err) => {
+            return Err(err).with_context(|| format!("failed to read {}", signature.display()))
+        }
+    };
+    tracing::info!(fingerprint = signer.fingerprint, "valid signature");
+
+    match output.format {
+        Format::Debug => match &signer.subkey {
+            Some(subkey) => writeln!(
+                output.stdout(),
+                "signed by {} (subkey {subkey})",
+                signer.fingerprint
+            )?,
+            None => writeln!(output.stdout(), "signed by {}", signer.fingerprint)?,
+        },
+        _ => output.serialize("signers", &signer)?,
+    }
+    Ok(())
+}
+
+/// How one backend did in `bench`.
+#[derive(Serialize)]
+struct BenchResult {
+    backend: &'static str,
+    /// The mean and the fastest time of one iteration in seconds.
+    mean: Option<f64>,
+    min: Option<f64>,
+    /// Bytes per second, based on the mean.
+    throughput: Option<f64>,
+    /// The most memory the process used while the backend parsed, in bytes. Only on Linux.
+    peak_rss: Option<u64>,
+    /// Why the backend couldn't parse the manifest.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Parse a manifest with every backend and print their timings as a table.
+fn bench(output: &Output, file: &Path, iterations: u32) -> Result<()> {
+    let content = read(file, Limits::default())?;
+    let input = content.trim_end();
+
+    let mut results = Vec::new();
+    for backend in Backend::ALL {
+        reset_peak_rss();
+        let mut times = Vec::new();
+        let mut error = None;
+        for _ in 0..iterations {
+            let start = Instant::now();
+            let parsed = backend.parse(hint::black_box(input));
+            times.push(start.elapsed());
+            if let Err(diag) = parsed {
+                error = Some(diag.to_string());
+                break;
+            }
+        }
+
+        let mean = (error.is_none()).then(|| times.iter().sum::<Duration>() / iterations);
+        results.push(BenchResult {
+            backend: backend.name(),
+            mean: mean.map(|mean| mean.as_secs_f64()),
+            min: mean.and(times.iter().min()).map(Duration::as_secs_f64),
+            throughput: mean.map(|mean| input.len() as f64 / mean.as_secs_f64()),
+            peak_rss: peak_rss(),
+            error,
+        });
+    }
+
+    if output.format != Format::Debug {
+        return output.serialize("benchmarks", &results);
+    }
+    let mut stdout = output.stdout();
+    writeln!(
+        stdout,
+        "{:<12} {:>12} {:>12} {:>14} {:>12}",
+        "backend", "mean", "min", "throughput", "peak rss"
+    )?;
+    for result in &results {
+        let time = |seconds: Option<f64>| {
+            seconds.map_or(String::from("-"), |s| {
+                format!("{:.2?}", Duration::from_secs_f64(s))
+            })
+        };
+        let throughput = result.throughput.map_or(String::from("-"), |throughput| {
+            format!("{}/s", HumanBytes(throughput as u64))
+        });
+        let peak_rss = result
+            .peak_rss
+            .map_or(String::from("-"), |rss| HumanBytes(rss).to_string());
+        write!(
+            stdout,
+            "{:<12} {:>12} {:>12} {throughput:>14} {peak_rss:>12}",
+            result.backend,
+            time(result.mean),
+            time(result.min),
+        )?;
+        match &result.error {
+            Some(error) => writeln!(stdout, "  failed: {error}")?,
+            None => writeln!(stdout)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Start measuring the peak memory usage of the process anew.
+fn reset_peak_rss() {
+    #[cfg(target_os = "linux")]
+    let _ = fs::write("/proc/self/clear_refs", "5");
+}
+
+/// The peak memory usage of the process since [reset_peak_rss], if the platform tells.
+fn peak_rss() -> Option<u64> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))?;
+    let kib = line.trim().strip_suffix("kB")?.trim().parse::<u64>().ok()?;
+    Some(kib * 1024)
+}
+
+/// The metadata of a package, as printed by `pkginfo`.
+/// Compare two versions, or sort the versions on stdin with `--sort`.
+#[cfg(feature = "alpm-types")]
+fn vercmp(output: &Output, versions: &[String], sort: bool) -> Result<()> {
+    let parse = |version: &str| {
+        version
+            .parse::<alpm_types::Version>()
+            .map_err(|err| Failure::Parse(format!("invalid version {version}: {err}")))
+    };
+    let mut stdout = output.stdout();
+    if !sort {
+        let [a, b] = versions else {
+            bail!("expected two versions");
+        };
+        writeln!(
+            stdout,
+            "{}",
+            alpm_types::Version::vercmp(&parse(a)?, &parse(b)?)
+        )?;
+        return Ok(());
+    }
+
+    let mut versions = io::stdin()
+        .lines()
+        .filter(|line| line.as_ref().map_or(true, |line| !line.trim().is_empty()))
+        .map(|line| {
+            let line = line?.trim().to_string();
+            Ok((parse(&line)?, line))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    versions.sort_by(|(a, _), (b, _)| a.cmp(b));
+    for (_, version) in versions {
+        writeln!(stdout, "{version}")?;
+    }
+    Ok(())
+}
 
+#[cfg(feature = "tar")]
+#[derive(Serialize)]
+struct PackageReport<'a> {
+    pkginfo: BTreeMap<&'a str, Vec<&'a str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    buildinfo: Option<BTreeMap<&'a str, Vec<&'a str>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mtree: Option<Stats<'a>>,
+}
+
+#[cfg(feature = "tar")]
+fn pkginfo(output: &Output, package: &Path, buildinfo: bool, with_mtree: bool) -> Result<()> {
+    let reader: Box<dyn io::Read> = if is_url(package) {
+        Box::new(fetch(package)?)
+    } else {
+        Box::new(File::open(package)?)
+    };
+    let metadata = mtree::archive::read_metadata(reader)
+        .with_context(|| format!("failed to read {}", package.display()))?;
+    let missing = |file: &str| anyhow::anyhow!("{} doesn't contain a {file}", package.display());
+    let pkginfo = metadata
+        .pkginfo
+        .as_deref()
+        .ok_or_else(|| missing(".PKGINFO"))?;
+    let mut report = PackageReport {
+        pkginfo: parse_info(output, package, ".PKGINFO", pkginfo)?,
+        buildinfo: None,
+        mtree: None,
+    };
+    if buildinfo {
+        let content = metadata
+            .buildinfo
+            .as_deref()
+            .ok_or_else(|| missing(".BUILDINFO"))?;
+        report.buildinfo = Some(parse_info(output, package, ".BUILDINFO", content)?);
+    }
+    let files = matches!(
+        output.format,
+        Format::Spdx | Format::Cyclonedx | Format::Pacman
+    );
+    let mut entries = Vec::new();
+    if with_mtree || files {
+        let content = metadata.mtree.as_deref().ok_or_else(|| missing(".MTREE"))?;
+        entries = resolve(output, &format!("{}/.MTREE", package.display()), content)?;
+        report.mtree = Some(Stats::from_entries(&entries));
+    }
+
+    if files {
+        let mut stdout = output.stdout();
+        match output.format {
+            Format::Spdx => mtree::sbom::write_spdx(&mut stdout, &report.pkginfo, &entries)?,
+            Format::Cyclonedx => {
+                mtree::sbom::write_cyclonedx(&mut stdout, &report.pkginfo, &entries)?
+            }
+            _ => {
+                let pkgname = output
+                    .pkgname
+                    .as_deref()
+                    .or_else(|| report.pkginfo.get("pkgname")?.first().copied())
+                    .ok_or_else(|| anyhow::anyhow!("{} has no pkgname", package.display()))?;
+                return export::write_file_list(stdout, pkgname, &entries);
+            }
+        }
+        writeln!(stdout)?;
+        return Ok(());
+    }
+    if output.format != Format::Debug {
+        return output.serialize("package metadata", &report);
+    }
+    let mut stdout = output.stdout();
+    let sections = [Some(&report.pkginfo), report.buildinfo.as_ref()];
+    for (index, info) in sections.into_iter().flatten().enumerate() {
+        if index > 0 {
+            writeln!(stdout)?;
+        }
+        for (key, values) in info {
+            writeln!(stdout, "{key}: {}", values.join(" "))?;
+        }
+    }
+    if let Some(stats) = &report.mtree {
+        write!(stdout, "\n{stats}")?;
+    }
     Ok(())
 }
 
-fn parser<'a>() -> impl Parser<'a, &'a str, Vec<Statement<'a>>, extra::Err<Rich<'a, char>>> {
-    use Statement::*;
-
-    // Parser for the very first line of the `.MTREE` file
-    let mtree = just("#")
-        .then(ascii::keyword("mtree"))
-        .then_ignore(newline())
-        .to(Init);
-
-    // Parser for the default properties behind a `/set` or `/unset` command
-    let default_properties = choice((
-        // `uid` and `gid` parser that expect a user/group id.
-        ascii::keyword("uid")
-            .then(just('='))
-            .ignore_then(text::digits(10).to_slice())
-            .map(|s: &str| DefaultProperty::Uid(s.parse().unwrap())),
-        ascii::keyword("gid")
-            .then(just('='))
-            .ignore_then(text::digits(10).to_slice())
-            .map(|s: &str| DefaultProperty::Gid(s.parse().unwrap())),
-        // `mode` parser which expects some octal digits
-        ascii::keyword("mode")
-            .then(just('='))
-            .ignore_then(text::digits(8).to_slice())
-            .map(|s: &str| DefaultProperty::Mode(s)),
-        // `type` parser which can be one of `file`, `dir` or `link`.
-        ascii::keyword("type")
-            .then(just('='))
-            .ignore_then(choice((
-                ascii::keyword("dir").to(PathType::Dir),
-                ascii::keyword("file").to(PathType::File),
-                ascii::keyword("link").to(PathType::Link),
-            )))
-            .map(DefaultProperty::Type),
-    ))
-    .padded()
-    .repeated()
-    .collect::<Vec<_>>();
-
-    // The `/set` parser
-    // Afterwards follows a whitespace delimited list of properties.
-    let set = just("/")
-        .then(ascii::keyword("set"))
-        .ignore_then(default_properties)
-        .map(Set);
-
-    // The `/unset` parser
-    // Afterwards follows a whitespace delimited list of properties.
-    let unset = just("/")
-        .ignored()
-        .then_ignore(ascii::keyword("unset"))
-        .to(Unset(Vec::new()));
-
-    // Parser for the properties behind a path line
-    let properties = choice((
-        // `mode` parser which expects some octal digits
-        ascii::keyword("mode")
-            .then(just('='))
-            .ignore_then(text::digits(8).to_slice())
-            .map(|s: &str| Property::Mode(s)),
-        // `Sha256Digest` parser which expects a hex digest
-        ascii::keyword("sha256digest")
-            .then(just('='))
-            .ignore_then(text::digits(16).to_slice())
-            .map(|s: &str| Property::Sha256Digest(s)),
-        // `size` parser which expects a decimal filesize in bytes
-        ascii::keyword("size")
-            .then(just('='))
-            .ignore_then(text::digits(10).to_slice())
-            .map(|s: &str| Property::Size(s.parse().unwrap())),
-        // `time` parser which expects a decimal epoch.
-        // For some reason, this is a floating point number.
-        // We just ignore any decimal places
-        ascii::keyword("time")
-            .then(just('='))
-            .ignore_then(text::digits(10).to_slice())
-            .then_ignore(just('.'))
-            .then_ignore(text::digits(10))
-            .map(|s: &str| Property::Time(s.parse().unwrap())),
-        // `type` parser which can be one of `file`, `dir` or `link`.
-        ascii::keyword("type")
-            .then(just('='))
-            .ignore_then(choice((
-                ascii::keyword("dir").to(PathType::Dir),
-                ascii::keyword("file").to(PathType::File),
-                ascii::keyword("link").to(PathType::Link),
-            )))
-            .map(Property::Type),
-        // `link` parser, which defines what a link links to.
-        ascii::keyword("link")
-            .then(just('='))
-            .ignore_then(none_of(" ").repeated().to_slice())
-            .map(Property::Link),
-    ))
-    .padded()
-    .repeated()
-    .collect::<Vec<_>>();
-
-    // Parse a path line.
-    // It starts with a `.` followed by some text, delimited by a whitespace.
-    // TODO: Theoretically whitespaces could be inside the path?
-    // Afterwards follows a whitespace delimited list of properties.
-    let path = just(".")
-        .then(none_of(" ").repeated().to_slice())
-        .to_slice()
-        .then(properties)
-        .map(|(path, properties)| Path { path, properties });
-
-    recursive(|_| choice((mtree, set, unset, path)).repeated().collect())
+/// Parse the `.PKGINFO` or `.BUILDINFO` of a package and report the error if it doesn't parse.
+#[cfg(feature = "tar")]
+fn parse_info<'a>(
+    output: &Output,
+    package: &Path,
+    file: &str,
+    content: &'a str,
+) -> Result<BTreeMap<&'a str, Vec<&'a str>>> {
+    mtree::parse_info(content).or_else(|diagnostic| {
+        let name = format!("{}/{file}", package.display());
+        output.report(&name, content, &diagnostic)?;
+        bail!(Failure::Parse(format!("failed to parse {name}")))
+    })
+}
+
+/// Something to watch for changes.
+#[derive(Clone)]
+enum Target {
+    /// A directory and everything below it.
+    Tree(PathBuf),
+    /// A directory and its direct children.
+    Dir(PathBuf),
+    /// A file, watched through its parent directory, so that editors that replace a file
+    /// instead of writing to it are noticed as well.
+    File(PathBuf),
+}
+
+impl Target {
+    /// The directory to watch and how.
+    fn watched(&self) -> (&Path, RecursiveMode) {
+        match self {
+            Self::Tree(dir) => (dir, RecursiveMode::Recursive),
+            Self::Dir(dir) => (dir, RecursiveMode::NonRecursive),
+            Self::File(file) => (file.parent().unwrap_or(file), RecursiveMode::NonRecursive),
+        }
+    }
+
+    /// Whether a changed path is relevant for this target.
+    fn matches(&self, path: &Path) -> bool {
+        match self {
+            Self::Tree(dir) => path.starts_with(dir),
+            Self::Dir(dir) => path == dir || path.parent() == Some(dir),
+            Self::File(file) => path == file,
+        }
+    }
+}
+
+/// Run a command and run it again whenever one of the paths changes, until interrupted.
+///
+/// Directories are watched recursively, files through their parent directory.
+/// Errors of the command are printed and don't stop watching.
+fn watch(paths: &[PathBuf], run: impl FnMut() -> Result<()>) -> Result<()> {
+    check_watchable(paths)?;
+    let mut targets = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            targets.push(Target::Tree(path.canonicalize()?));
+        } else {
+            for file in expand(slice::from_ref(path))? {
+                targets.push(Target::File(file.canonicalize()?));
+            }
+        }
+    }
+
+    watch_targets(|| Ok(targets.clone()), run)
+}
+
+/// Verify a manifest and verify it again whenever it or one of its paths changes.
+///
+/// Only the manifest and the directories that contain its paths are watched, each without its
+/// subdirectories, so a manifest with the default root of `/` doesn't watch the whole
+/// filesystem. The directories are looked up again whenever the manifest changes.
+fn watch_verify(
+    output: &Output,
+    manifest: &Path,
+    root: &Path,
+    options: &VerifyOptions,
+) -> Result<()> {
+    check_watchable(slice::from_ref(&manifest.to_path_buf()))?;
+    let manifest = manifest.canonicalize()?;
+    let filter = path_filter(&options.paths)?;
+    let targets = || {
+        let mut targets = vec![Target::File(manifest.clone())];
+        // A manifest that doesn't parse is reported by `verify`, and watched until it's fixed.
+        let Ok(parsed) = mtree::parse_file(&manifest, output.parse_options(), output.duplicates)
+        else {
+            return Ok(targets);
+        };
+        let dirs = parsed
+            .entries
+            .iter()
+            .filter(|entry| filter.as_ref().is_none_or(|filter| filter.matches(entry)))
+            .filter_map(|entry| {
+                mtree::verify::fs_path(root, &entry.path)
+                    .ok()?
+                    .parent()?
+                    .canonicalize()
+                    .ok()
+            })
+            .collect::<BTreeSet<_>>();
+        tracing::info!(dirs = dirs.len(), "watching directories");
+        targets.extend(dirs.into_iter().map(Target::Dir));
+        Ok(targets)
+    };
+
+    watch_targets(targets, || {
+        output.finish(verify(output, &manifest, root, options))
+    })
+}
+
+/// Refuse to watch stdin and URLs, which can't change.
+fn check_watchable(paths: &[PathBuf]) -> Result<()> {
+    if paths.iter().any(|path| is_stdin(path)) {
+        bail!("stdin can't be watched");
+    }
+    if let Some(url) = paths.iter().find(|path| is_url(path)) {
+        bail!("{} can't be watched", url.display());
+    }
+    Ok(())
+}
+
+/// Run a command and run it again whenever one of the targets changes, until interrupted.
+/// The targets are looked up again before every run.
+fn watch_targets(
+    mut targets: impl FnMut() -> Result<Vec<Target>>,
+    mut run: impl FnMut() -> Result<()>,
+) -> Result<()> {
+    loop {
+        let targets = targets()?;
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        let mut watched = BTreeSet::new();
+        for target in &targets {
+            let (path, mode) = target.watched();
+            // A directory may be watched non-recursively for several files.
+            if watched.insert((path.to_path_buf(), mode == RecursiveMode::Recursive)) {
+                watcher.watch(path, mode)?;
+            }
+        }
+        let relevant = |event: &Event| {
+            !event.kind.is_access()
+                && event
+                    .paths
+                    .iter()
+                    .any(|path| targets.iter().any(|target| target.matches(path)))
+        };
+
+        if let Err(err) = run() {
+            eprintln!("Error: {err:#}");
+        }
+        loop {
+            if relevant(&receiver.recv()??) {
+                break;
+            }
+        }
+        // Let a burst of events, e.g. of an extracted package, settle before running again.
+        while receiver.recv_timeout(Duration::from_millis(200)).is_ok() {}
+        eprintln!("--- changed, running again");
+    }
+}
+
+/// A failure with its own exit code, other errors exit with [EXIT_ERROR].
+#[derive(Debug)]
+enum Failure {
+    /// Lints, audit findings or verification mismatches.
+    Findings(String),
+    /// A manifest doesn't parse.
+    Parse(String),
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Findings(message) | Self::Parse(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for Failure {}
+
+/// The exit code of an error.
+fn exit_code(err: &anyhow::Error) -> u8 {
+    match err.downcast_ref() {
+        Some(Failure::Findings(_)) => return EXIT_FINDINGS,
+        Some(Failure::Parse(_)) => return EXIT_PARSE,
+        None => {}
+    }
+    let unparsable = err.chain().any(|cause| {
+        matches!(
+            cause.downcast_ref(),
+            Some(ParseFileError::Syntax(_) | ParseFileError::Limit(_))
+        )
+    });
+
+    if unparsable {
+        EXIT_PARSE
+    } else {
+        EXIT_ERROR
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let error_format = cli.output.error_format;
+    match run(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            match error_format {
+                ErrorFormat::Human => eprintln!("Error: {err:?}"),
+                ErrorFormat::Json => {
+                    let report = ErrorReport {
+                        file: None,
+                        diagnostic: &Diagnostic::error("failed", format!("{err:#}")),
+                    };
+                    eprintln!("{}", serde_json::to_string(&report).unwrap_or_default());
+                }
+            }
+            ExitCode::from(exit_code(&err))
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<()> {
+    let Cli {
+        mut output,
+        logging,
+        jobs,
+        command,
+    } = cli;
+    logging.init(output.color(io::stderr()));
+    output.no_progress |= logging.verbose > 0;
+    let result = match command {
+        Command::Parse {
+            watch: true,
+            from,
+            files,
+        } => self::watch(&files, || output.finish(parse(&output, from, &files, jobs))),
+        Command::Parse { from, files, .. } => parse(&output, from, &files, jobs),
+        Command::Validate { strictness, files } => validate(&output, strictness, &files),
+        Command::Get { manifest, path } => get(&output, &manifest, &path),
+        Command::Lint { fail_on, files } => lint(&output, fail_on, &files),
+        Command::Convert {
+            from,
+            to,
+            file,
+            normalize,
+        } => convert(&output, from, to, &file, &normalize.options()),
+        Command::Verify {
+            manifest,
+            root,
+            #[cfg(feature = "digests")]
+            digests,
+            exclude,
+            paths,
+            watch,
+        } => {
+            let options = VerifyOptions {
+                #[cfg(feature = "digests")]
+                digests,
+                #[cfg(feature = "digests")]
+                jobs,
+                exclude,
+                paths,
+            };
+            if watch {
+                watch_verify(&output, &manifest, &root, &options)
+            } else {
+                verify(&output, &manifest, &root, &options)
+            }
+        }
+        Command::Create(args) => create(&output, args, jobs),
+        #[cfg(feature = "digests")]
+        Command::Refresh {
+            manifest,
+            root,
+            digests,
+            exclude,
+        } => refresh(&output, &manifest, &root, digests, &exclude, jobs),
+        Command::Diff {
+            old,
+            new,
+            normalize,
+        } => diff(&output, &old, &new, &normalize.options()),
+        Command::Check { dbpath, root } => check(&output, &dbpath, &root, jobs),
+        Command::Stats { files } => stats(&output, &files, jobs),
+        Command::Bench { file, iterations } => bench(&output, &file, iterations),
+        #[cfg(feature = "signatures")]
+        Command::VerifySignature {
+            file,
+            signature,
+            keyring,
+        } => verify_signature(&output, &file, signature, &keyring),
+        #[cfg(feature = "tar")]
+        Command::Pkginfo {
+            package,
+            buildinfo,
+            mtree,
+        } => pkginfo(&output, &package, buildinfo, mtree),
+        #[cfg(feature = "alpm-types")]
+        Command::Vercmp { versions, sort } => vercmp(&output, &versions, sort),
+    };
+    output.finish(result)
 }
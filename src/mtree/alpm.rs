@@ -0,0 +1,71 @@
+//! Conversions to the newtypes of the `alpm-types` crate, for ALPM tooling that wants validated
+//! names, versions, architectures and checksums instead of strings.
+
+use std::str::FromStr;
+
+use alpm_types::{Architecture, FullVersion, Md5Checksum, Name, Sha1Checksum, Sha256Checksum};
+
+use super::{localdb, pkginfo::Info, Diagnostic, Entry};
+
+/// The name, version and architecture of a package, read from its `.PKGINFO`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageInfo {
+    pub name: Name,
+    pub version: FullVersion,
+    pub arch: Architecture,
+}
+
+impl PackageInfo {
+    /// Read the `pkgname`, `pkgver` and `arch` of a `.PKGINFO` parsed with
+    /// [parse_info](super::parse_info). A missing or invalid field is an error.
+    pub fn from_info(info: &Info) -> Result<Self, Diagnostic> {
+        Ok(Self {
+            name: field(info, "pkgname")?,
+            version: field(info, "pkgver")?,
+            arch: field(info, "arch")?,
+        })
+    }
+}
+
+/// Parse the first value of a field.
+fn field<T>(info: &Info, key: &str) -> Result<T, Diagnostic>
+where
+    T: FromStr<Err = alpm_types::Error>,
+{
+    let value = info
+        .get(key)
+        .and_then(|values| values.first())
+        .ok_or_else(|| Diagnostic::error("missing-field", format!("no {key} field")))?;
+    value.parse().map_err(|err| {
+        Diagnostic::error("invalid-field", format!("invalid {key} {value:?}: {err}"))
+    })
+}
+
+impl localdb::Package {
+    /// The name of the package as an [alpm_types::Name].
+    pub fn alpm_name(&self) -> Result<Name, alpm_types::Error> {
+        self.name.parse()
+    }
+
+    /// The version of the package, with its release and epoch, as an [alpm_types::FullVersion].
+    pub fn alpm_version(&self) -> Result<FullVersion, alpm_types::Error> {
+        self.version.parse()
+    }
+}
+
+impl Entry<'_> {
+    /// The MD5 digest of the entry, if it records one.
+    pub fn md5_checksum(&self) -> Option<Result<Md5Checksum, alpm_types::Error>> {
+        self.md5digest.as_deref().map(str::parse)
+    }
+
+    /// The SHA1 digest of the entry, if it records one.
+    pub fn sha1_checksum(&self) -> Option<Result<Sha1Checksum, alpm_types::Error>> {
+        self.sha1digest.as_deref().map(str::parse)
+    }
+
+    /// The SHA256 digest of the entry, if it records one.
+    pub fn sha256_checksum(&self) -> Option<Result<Sha256Checksum, alpm_types::Error>> {
+        self.sha256digest.as_deref().map(str::parse)
+    }
+}
@@ -0,0 +1,292 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::{self, BufRead, BufReader, Read},
+    path::Path,
+};
+
+use flate2::read::GzDecoder;
+use xz2::read::XzDecoder;
+
+use super::{
+    digest::hash_reader,
+    sniff::Compression,
+    verify::{compare, compare_digests, Found},
+    DigestAlgorithm, DuplicatePolicy, Entry, Gid, Limits, Mismatch, MismatchKind, Mode,
+    ParseOptions, PathType, Timestamp, Uid,
+};
+
+/// Wrap a reader so that it transparently decompresses its data.
+/// The compression (gzip, zstd or xz) is detected by the magic bytes at the start, uncompressed
+/// data is passed through.
+pub fn decompress<'r, R: Read + 'r>(reader: R) -> io::Result<Box<dyn Read + 'r>> {
+    let mut reader = BufReader::new(reader);
+    match Compression::detect(reader.fill_buf()?) {
+        Some(Compression::Gzip) => Ok(Box::new(GzDecoder::new(reader))),
+        Some(Compression::Zstd) => Ok(Box::new(zstd::Decoder::with_buffer(reader)?)),
+        Some(Compression::Xz) => Ok(Box::new(XzDecoder::new(reader))),
+        None => Ok(Box::new(reader)),
+    }
+}
+
+/// Read the decompressed `.MTREE` out of a package, e.g. a `.pkg.tar.zst`.
+///
+/// The archive is only read up to the `.MTREE` member, which makepkg puts at the start.
+/// Returns `None` if the package doesn't contain one. The manifest is capped at the default
+/// [Limits::max_decompressed_size].
+pub fn read_mtree<R: Read>(package: R) -> io::Result<Option<String>> {
+    let mut archive = tar::Archive::new(decompress(package)?);
+    for member in archive.entries()? {
+        let member = member?;
+        if manifest_path(&member.path()?) == "./.MTREE" {
+            return Limits::default()
+                .read_to_string(decompress(member)?)
+                .map(Some);
+        }
+    }
+
+    Ok(None)
+}
+
+/// Read and parse the `.MTREE` of a package file in one call.
+///
+/// A package without a `.MTREE` is a [io::ErrorKind::NotFound] error, a manifest that
+/// doesn't parse is [io::ErrorKind::InvalidData].
+pub fn parse_package(path: &Path) -> io::Result<Vec<Entry<'static>>> {
+    let content = read_mtree(File::open(path)?)?.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} doesn't contain a .MTREE", path.display()),
+        )
+    })?;
+
+    super::bulk::parse_owned(
+        &content,
+        ParseOptions::default(),
+        DuplicatePolicy::default(),
+    )
+    .map(|parsed| parsed.entries)
+    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+/// The metadata files of a package.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Metadata {
+    /// The `.PKGINFO` with the name, version and dependencies of the package.
+    pub pkginfo: Option<String>,
+    /// The `.BUILDINFO` with the environment the package was built in.
+    pub buildinfo: Option<String>,
+    /// The decompressed `.MTREE`.
+    pub mtree: Option<String>,
+}
+
+/// Read the `.PKGINFO`, `.BUILDINFO` and `.MTREE` out of a package.
+///
+/// makepkg puts them at the start, so the archive is only read until all three were found.
+/// Each file is capped at the default [Limits::max_decompressed_size].
+pub fn read_metadata<R: Read>(package: R) -> io::Result<Metadata> {
+    let mut metadata = Metadata::default();
+    let mut archive = tar::Archive::new(decompress(package)?);
+    for member in archive.entries()? {
+        let member = member?;
+        let file = match manifest_path(&member.path()?).as_str() {
+            "./.PKGINFO" => &mut metadata.pkginfo,
+            "./.BUILDINFO" => &mut metadata.buildinfo,
+            "./.MTREE" => &mut metadata.mtree,
+            _ => continue,
+        };
+        *file = Some(Limits::default().read_to_string(decompress(member)?)?);
+        if metadata.pkginfo.is_some() && metadata.buildinfo.is_some() && metadata.mtree.is_some() {
+            break;
+        }
+    }
+
+    Ok(metadata)
+}
+
+/// Check the given entries against the members of a (possibly compressed) tar archive.
+///
+/// The archive is streamed, so nothing is extracted to disk. Digests are computed from the data
+/// of each member while reading it. The `.MTREE` member itself is skipped, since a manifest can't
+/// contain itself.
+///
+/// A hardlink member has no data of its own, it's checked with the size and the digests of the
+/// earlier member that it links to. Members of a type that a manifest can't describe, e.g. GNU
+/// sparse files, are reported as [MismatchKind::Inaccessible].
+pub fn verify_archive<R: Read>(entries: &[Entry], archive: R) -> io::Result<Vec<Mismatch>> {
+    let expected: HashMap<&str, &Entry> = entries
+        .iter()
+        .map(|entry| (entry.path.as_ref(), entry))
+        .collect();
+    // Every member is hashed with all algorithms of the manifest, so that hardlinks to it can
+    // be checked with whichever digests they record.
+    let algorithms: Vec<_> = DigestAlgorithm::ALL
+        .iter()
+        .copied()
+        .filter(|algorithm| {
+            entries
+                .iter()
+                .any(|entry| algorithm.expected(entry).is_some())
+        })
+        .collect();
+    // The size and the digests of the regular files so far, for the hardlinks to them.
+    let mut files: HashMap<String, (u64, Vec<_>)> = HashMap::new();
+    let mut seen = HashSet::<String>::new();
+    let mut mismatches = Vec::new();
+
+    let mut archive = tar::Archive::new(decompress(archive)?);
+    for member in archive.entries()? {
+        let mut member = member?;
+        let path = manifest_path(&member.path()?);
+        if path == "./.MTREE" {
+            continue;
+        }
+
+        let entry_type = member.header().entry_type();
+        let Some(path_type) = member_type(entry_type) else {
+            seen.insert(path.clone());
+            mismatches.push(Mismatch {
+                path,
+                kind: MismatchKind::Inaccessible {
+                    error: format!("unsupported archive member type {entry_type:?}"),
+                },
+            });
+            continue;
+        };
+        let mut found = found(&mut member, path_type)?;
+        let digests = if entry_type.is_hard_link() {
+            let target = found
+                .link
+                .take()
+                .map(|link| manifest_path(Path::new(&link)));
+            match target.and_then(|target| files.get(&target)) {
+                Some((size, digests)) => {
+                    found.size = *size;
+                    digests.clone()
+                }
+                None => {
+                    seen.insert(path.clone());
+                    mismatches.push(Mismatch {
+                        path,
+                        kind: MismatchKind::Inaccessible {
+                            error: "hardlink to a member that isn't an earlier file".to_string(),
+                        },
+                    });
+                    continue;
+                }
+            }
+        } else if path_type == PathType::File {
+            let digests = if algorithms.is_empty() {
+                Vec::new()
+            } else {
+                hash_reader(&mut member, &algorithms)?
+            };
+            files.insert(path.clone(), (found.size, digests.clone()));
+            digests
+        } else {
+            Vec::new()
+        };
+
+        let Some(entry) = expected.get(path.as_str()) else {
+            mismatches.push(Mismatch {
+                path,
+                kind: MismatchKind::Extra,
+            });
+            continue;
+        };
+        seen.insert(path.clone());
+
+        let kinds = compare(entry, &found);
+        let type_matches = !kinds
+            .iter()
+            .any(|kind| matches!(kind, MismatchKind::Type { .. }));
+        mismatches.extend(kinds.into_iter().map(|kind| Mismatch {
+            path: path.clone(),
+            kind,
+        }));
+
+        if type_matches {
+            mismatches.extend(
+                compare_digests(entry, digests)
+                    .into_iter()
+                    .map(|kind| Mismatch {
+                        path: path.clone(),
+                        kind,
+                    }),
+            );
+        }
+    }
+
+    mismatches.extend(
+        entries
+            .iter()
+            .filter(|entry| !seen.contains(entry.path.as_ref()))
+            .map(|entry| Mismatch {
+                path: entry.path.to_string(),
+                kind: MismatchKind::Missing,
+            }),
+    );
+
+    Ok(mismatches)
+}
+
+/// Convert the path of an archive member to the form used in manifests, e.g. `./usr/bin`.
+fn manifest_path(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    let path = path.trim_start_matches("./").trim_end_matches('/');
+    match path {
+        "" | "." => String::from("."),
+        path => format!("./{path}"),
+    }
+}
+
+/// The type of an archive member, hardlinks are files. `None` for types that a manifest can't
+/// describe.
+fn member_type(entry_type: tar::EntryType) -> Option<PathType> {
+    match entry_type {
+        tar::EntryType::Regular | tar::EntryType::Continuous | tar::EntryType::Link => {
+            Some(PathType::File)
+        }
+        tar::EntryType::Directory => Some(PathType::Dir),
+        tar::EntryType::Symlink => Some(PathType::Link),
+        tar::EntryType::Block => Some(PathType::Block),
+        tar::EntryType::Char => Some(PathType::Char),
+        tar::EntryType::Fifo => Some(PathType::Fifo),
+        _ => None,
+    }
+}
+
+/// The properties of an archive member of the given type. The link of a hardlink is the path
+/// of the member that it links to.
+fn found<R: Read>(member: &mut tar::Entry<R>, path_type: PathType) -> io::Result<Found> {
+    let header = member.header();
+    let size = header.size()?;
+    let mode = Mode::from_bits_truncate(header.mode()?);
+    let uid = Uid(header.uid()? as u32);
+    let gid = Gid(header.gid()? as u32);
+    let mut time = Timestamp::new(header.mtime()? as i64, 0);
+    let link = member
+        .link_name()?
+        .map(|link| link.to_string_lossy().into_owned());
+
+    // Pax headers may carry the modification time with sub-second precision.
+    if let Some(extensions) = member.pax_extensions()? {
+        for extension in extensions.flatten() {
+            if extension.key() == Ok("mtime") {
+                if let Some(mtime) = extension.value().ok().and_then(|v| v.parse().ok()) {
+                    time = mtime;
+                }
+            }
+        }
+    }
+
+    Ok(Found {
+        path_type,
+        size,
+        mode: Some(mode),
+        uid: Some(uid),
+        gid: Some(gid),
+        time,
+        link,
+    })
+}
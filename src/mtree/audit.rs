@@ -0,0 +1,148 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use super::{Entry, PathType, Severity};
+
+/// A security relevant property of a single entry.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Finding {
+    /// The path of the entry as written in the manifest.
+    pub path: String,
+    pub kind: FindingKind,
+    pub severity: Severity,
+}
+
+/// What exactly was found.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case", tag = "kind")]
+pub enum FindingKind {
+    /// Anybody may write to the path.
+    WorldWritable,
+    Setuid,
+    Setgid,
+    /// A relative symlink that points above the root of the package.
+    SymlinkEscape {
+        target: String,
+    },
+    /// The path contains a `..` component.
+    ParentComponent,
+    /// The path resolves to an absolute path, e.g. `.//etc/shadow`.
+    AbsolutePath,
+    /// A block or character device.
+    DeviceNode,
+}
+
+impl FindingKind {
+    /// The default severity of this kind of finding.
+    ///
+    /// World-writable directories with the sticky bit (like `/tmp`) are only informational.
+    fn severity(&self, entry: &Entry) -> Severity {
+        match self {
+            Self::WorldWritable if entry.mode.is_some_and(|mode| mode.is_sticky()) => {
+                Severity::Info
+            }
+            Self::WorldWritable | Self::Setuid | Self::Setgid | Self::DeviceNode => {
+                Severity::Warning
+            }
+            Self::SymlinkEscape { .. } | Self::ParentComponent | Self::AbsolutePath => {
+                Severity::Error
+            }
+        }
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}: {}", self.severity, self.path, self.kind)
+    }
+}
+
+impl fmt::Display for FindingKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::WorldWritable => write!(f, "world-writable"),
+            Self::Setuid => write!(f, "setuid"),
+            Self::Setgid => write!(f, "setgid"),
+            Self::SymlinkEscape { target } => {
+                write!(f, "symlink to {target} escapes the package root")
+            }
+            Self::ParentComponent => write!(f, "path contains `..`"),
+            Self::AbsolutePath => write!(f, "absolute path"),
+            Self::DeviceNode => write!(f, "device node"),
+        }
+    }
+}
+
+/// Check resolved entries for security relevant properties, e.g. before installing a package.
+///
+/// Symlinks are never reported as world-writable, since their mode is meaningless.
+/// Absolute symlink targets are fine, as they point into the root the package is installed to.
+pub fn audit(entries: &[Entry]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for entry in entries {
+        let mut kinds = Vec::new();
+        let path = entry.path.strip_prefix('.').unwrap_or(&entry.path);
+        if path.starts_with("//") {
+            kinds.push(FindingKind::AbsolutePath);
+        }
+        if path.split('/').any(|component| component == "..") {
+            kinds.push(FindingKind::ParentComponent);
+        }
+        if let Some(mode) = entry.mode {
+            if mode.is_world_writable() && entry.path_type != Some(PathType::Link) {
+                kinds.push(FindingKind::WorldWritable);
+            }
+            if mode.is_setuid() {
+                kinds.push(FindingKind::Setuid);
+            }
+            if mode.is_setgid() {
+                kinds.push(FindingKind::Setgid);
+            }
+        }
+        if let Some(target) = &entry.link {
+            if escapes_root(path, target) {
+                kinds.push(FindingKind::SymlinkEscape {
+                    target: target.to_string(),
+                });
+            }
+        }
+        if matches!(entry.path_type, Some(PathType::Block | PathType::Char)) {
+            kinds.push(FindingKind::DeviceNode);
+        }
+
+        findings.extend(kinds.into_iter().map(|kind| Finding {
+            path: entry.path.to_string(),
+            severity: kind.severity(entry),
+            kind,
+        }));
+    }
+
+    findings
+}
+
+/// Whether a relative link target climbs above the root, relative to the directory of the
+/// link at `path` (without the leading `.`).
+fn escapes_root(path: &str, target: &str) -> bool {
+    if target.starts_with('/') {
+        return false;
+    }
+
+    let mut parent = path.split('/').collect::<Vec<_>>();
+    parent.pop();
+    let mut depth = 0isize;
+    for component in parent.into_iter().chain(target.split('/')) {
+        match component {
+            "" | "." => {}
+            ".." => {
+                depth -= 1;
+                if depth < 0 {
+                    return true;
+                }
+            }
+            _ => depth += 1,
+        }
+    }
+
+    false
+}
@@ -0,0 +1,123 @@
+use std::{fmt, ops::Range, str::FromStr};
+
+use anyhow::bail;
+use chumsky::Parser;
+
+use super::{handrolled::HandRolledBackend, nom_parser::NomBackend, Statement};
+
+/// A parser implementation that turns a manifest into statements.
+///
+/// This crate compares different approaches to parsing mtree files, every backend has to
+/// produce the same statements for the same input.
+pub trait MtreeBackend {
+    /// The name of the backend, e.g. `chumsky`.
+    fn name(&self) -> &'static str;
+
+    /// Parse a whole manifest.
+    fn parse<'a>(&self, input: &'a str) -> Result<Vec<Statement<'a>>, Diag>;
+}
+
+/// A parse error, independent of the backend that produced it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diag {
+    /// The byte range of the input that the error refers to.
+    pub span: Range<usize>,
+    pub message: String,
+}
+
+impl Diag {
+    /// An error about the character at the given byte offset of the input.
+    pub(crate) fn at(input: &str, offset: usize) -> Self {
+        match input[offset..].chars().next() {
+            Some(c) => Self {
+                span: offset..offset + c.len_utf8(),
+                message: format!("found {c:?}"),
+            },
+            None => Self {
+                span: offset..offset,
+                message: String::from("found end of input"),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Diag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at {}..{}",
+            self.message, self.span.start, self.span.end
+        )
+    }
+}
+
+impl std::error::Error for Diag {}
+
+/// The parser built with [chumsky](https://docs.rs/chumsky), see [super::parser].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ChumskyBackend;
+
+impl MtreeBackend for ChumskyBackend {
+    fn name(&self) -> &'static str {
+        "chumsky"
+    }
+
+    /// Only the first of the errors that chumsky recovers from is reported.
+    fn parse<'a>(&self, input: &'a str) -> Result<Vec<Statement<'a>>, Diag> {
+        super::parser().parse(input).into_result().map_err(|errs| {
+            let err = &errs[0];
+            Diag {
+                span: err.span().into_range(),
+                message: err.to_string(),
+            }
+        })
+    }
+}
+
+/// All available backends, to select one at runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Backend {
+    #[default]
+    Chumsky,
+    Nom,
+    HandRolled,
+}
+
+impl Backend {
+    pub const ALL: &'static [Backend] = &[Self::Chumsky, Self::Nom, Self::HandRolled];
+
+    fn backend(&self) -> &'static dyn MtreeBackend {
+        match self {
+            Self::Chumsky => &ChumskyBackend,
+            Self::Nom => &NomBackend,
+            Self::HandRolled => &HandRolledBackend,
+        }
+    }
+}
+
+impl MtreeBackend for Backend {
+    fn name(&self) -> &'static str {
+        self.backend().name()
+    }
+
+    fn parse<'a>(&self, input: &'a str) -> Result<Vec<Statement<'a>>, Diag> {
+        self.backend().parse(input)
+    }
+}
+
+impl FromStr for Backend {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match Self::ALL.iter().find(|backend| backend.name() == s) {
+            Some(backend) => Ok(*backend),
+            None => bail!("unknown parser backend: {s}"),
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
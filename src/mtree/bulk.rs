@@ -0,0 +1,190 @@
+use std::{
+    fmt,
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+use rayon::prelude::*;
+
+#[cfg(feature = "tar")]
+pub(crate) use super::archive::decompress;
+#[cfg(not(feature = "tar"))]
+use super::sniff::Compression;
+use super::{Diagnostic, DuplicatePolicy, Entry, LimitExceeded, Limits, ParseOptions, Progress};
+
+/// Errors that can occur when reading and parsing a manifest file.
+#[derive(Debug)]
+pub enum ParseFileError {
+    /// The file couldn't be read.
+    Io(io::Error),
+    /// The manifest isn't valid, or its content exceeds the limits.
+    Syntax(Diagnostic),
+    /// The file exceeds the limits while it's read.
+    Limit(LimitExceeded),
+}
+
+impl fmt::Display for ParseFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Syntax(diagnostic) => write!(f, "{diagnostic}"),
+            Self::Limit(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for ParseFileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Syntax(diagnostic) => Some(diagnostic),
+            Self::Limit(err) => Some(err),
+        }
+    }
+}
+
+/// Exceeding [Limits::max_decompressed_size] while reading is a [ParseFileError::Limit].
+impl From<io::Error> for ParseFileError {
+    fn from(err: io::Error) -> Self {
+        match err
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<LimitExceeded>())
+        {
+            Some(limit) => Self::Limit(*limit),
+            None => Self::Io(err),
+        }
+    }
+}
+
+impl From<LimitExceeded> for ParseFileError {
+    fn from(err: LimitExceeded) -> Self {
+        Self::Limit(err)
+    }
+}
+
+/// The resolved entries of a manifest file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedFile {
+    pub entries: Vec<Entry<'static>>,
+    /// Warnings about the deviations that the strictness tolerated and about the duplicate paths
+    /// that the policy resolved, see [ParseOptions::resolve].
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Read and parse a single manifest file into its entries, resolved with the policy for
+/// duplicate paths.
+///
+/// Compressed files, like the `.MTREE` in a package, are decompressed transparently, see [read].
+pub fn parse_file(
+    path: &Path,
+    options: ParseOptions,
+    duplicates: DuplicatePolicy,
+) -> Result<ParsedFile, ParseFileError> {
+    parse_owned(&read_file(path, options.limits)?, options, duplicates)
+}
+
+/// Read a manifest file without parsing it, e.g. to show parse errors with their context.
+#[tracing::instrument(skip(limits), fields(path = %path.display()), err)]
+pub fn read_file(path: &Path, limits: Limits) -> Result<String, ParseFileError> {
+    read(File::open(path)?, limits)
+}
+
+/// Read a whole manifest from a stream, e.g. stdin.
+///
+/// Compressed input is detected by its first bytes and decompressed transparently. Gzip is
+/// always supported, zstd and xz only with the `tar` feature.
+pub fn read<R: Read>(reader: R, limits: Limits) -> Result<String, ParseFileError> {
+    let content = limits.read_to_string(decompress(reader)?)?;
+    tracing::debug!(bytes = content.len(), "read manifest");
+    Ok(content)
+}
+
+/// Like [read], but fail unless the manifest is plain UTF-8 without a byte order mark, instead
+/// of converting it.
+pub fn read_strict<R: Read>(reader: R, limits: Limits) -> Result<String, ParseFileError> {
+    let bytes = limits.read_to_end(decompress(reader)?)?;
+    Ok(super::encoding::decode_strict(bytes)?)
+}
+
+/// Decompress gzip streams, any other uncompressed input is passed through.
+#[cfg(not(feature = "tar"))]
+pub(crate) fn decompress<'r, R: Read + 'r>(reader: R) -> io::Result<Box<dyn Read + 'r>> {
+    use std::io::{BufRead, BufReader};
+
+    let mut reader = BufReader::new(reader);
+    match Compression::detect(reader.fill_buf()?) {
+        Some(Compression::Gzip) => Ok(Box::new(flate2::read::GzDecoder::new(reader))),
+        Some(compression) => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("{compression} decompression requires the `tar` feature"),
+        )),
+        None => Ok(Box::new(reader)),
+    }
+}
+
+/// Parse many manifest files concurrently, e.g. all `.MTREE`s of a repository.
+///
+/// The files are parsed on a dedicated thread pool with `jobs` threads, `0` uses one per CPU.
+/// There is one result per path, in the same order as the paths.
+pub fn parse_many<P: AsRef<Path> + Sync>(
+    paths: &[P],
+    jobs: usize,
+    options: ParseOptions,
+    duplicates: DuplicatePolicy,
+) -> Result<Vec<Result<ParsedFile, ParseFileError>>, rayon::ThreadPoolBuildError> {
+    parse_many_with_progress(paths, jobs, options, duplicates, &())
+}
+
+/// Like [parse_many], but report every parsed file with its decompressed size to `progress`.
+pub fn parse_many_with_progress<P: AsRef<Path> + Sync>(
+    paths: &[P],
+    jobs: usize,
+    options: ParseOptions,
+    duplicates: DuplicatePolicy,
+    progress: &dyn Progress,
+) -> Result<Vec<Result<ParsedFile, ParseFileError>>, rayon::ThreadPoolBuildError> {
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(jobs).build()?;
+    tracing::info!(
+        files = paths.len(),
+        threads = pool.current_num_threads(),
+        "parsing manifests"
+    );
+
+    progress.start(paths.len() as u64, 0);
+    let results = pool.install(|| {
+        paths
+            .par_iter()
+            .map(|path| {
+                let content = read_file(path.as_ref(), options.limits);
+                progress.advance(content.as_ref().map_or(0, |content| content.len() as u64));
+                parse_owned(&content?, options, duplicates)
+            })
+            .collect()
+    });
+    progress.finish();
+
+    Ok(results)
+}
+
+/// Parse and resolve a manifest into entries that don't borrow from the input, with the same
+/// limits, strictness and policy for duplicate paths as [ParseOptions::resolve].
+#[tracing::instrument(skip_all, fields(bytes = content.len(), backend = ?options.backend, ?duplicates))]
+pub(crate) fn parse_owned(
+    content: &str,
+    options: ParseOptions,
+    duplicates: DuplicatePolicy,
+) -> Result<ParsedFile, ParseFileError> {
+    let resolved = options
+        .resolve(content, duplicates)
+        .map_err(ParseFileError::Syntax)?;
+
+    Ok(ParsedFile {
+        entries: resolved
+            .entries
+            .into_iter()
+            .map(Entry::into_owned)
+            .collect(),
+        diagnostics: resolved.diagnostics,
+    })
+}
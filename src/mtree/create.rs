@@ -0,0 +1,291 @@
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    env,
+    fs::{self, Metadata},
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use super::{DigestAlgorithm, Entry, Excludes, PathType, Progress, Timestamp};
+
+/// Options for [create].
+#[derive(Debug, Clone, Default)]
+pub struct CreateOptions {
+    /// The digests to compute for every file.
+    /// Computing digests requires the `digests` feature.
+    pub digests: Vec<DigestAlgorithm>,
+    /// Clamp all modification times that are newer than this epoch to it.
+    /// See <https://reproducible-builds.org/specs/source-date-epoch/>.
+    pub source_date_epoch: Option<i64>,
+    /// Don't record modification times at all.
+    pub omit_time: bool,
+    /// Don't record uid and gid, which depend on the user that builds the tree.
+    pub omit_owner: bool,
+    /// Paths that are skipped while walking the tree. Excluded directories aren't entered.
+    pub excludes: Option<Excludes>,
+    /// The number of threads to compute digests with, `0` uses one thread per CPU.
+    pub threads: usize,
+    /// Receives every hashed file with its size.
+    pub progress: Option<Arc<dyn Progress>>,
+}
+
+impl CreateOptions {
+    /// Options for reproducible manifests, where two builds of the same tree produce
+    /// byte-identical output: times are clamped to `SOURCE_DATE_EPOCH` if it is set and the
+    /// owner isn't recorded.
+    pub fn reproducible() -> Self {
+        Self {
+            source_date_epoch: source_date_epoch(),
+            omit_owner: true,
+            ..Self::default()
+        }
+    }
+}
+
+/// The value of the `SOURCE_DATE_EPOCH` environment variable, if it is set to a valid epoch.
+pub fn source_date_epoch() -> Option<i64> {
+    env::var("SOURCE_DATE_EPOCH").ok()?.trim().parse().ok()
+}
+
+/// Generate the entries of a manifest for everything below `root`, like
+/// `bsdtar --format=mtree` does.
+///
+/// The paths are relative to `root` (e.g. `./usr/bin/foo`), the root itself isn't included.
+/// Directories are walked depth-first with their contents sorted byte-wise by name, so the
+/// order doesn't depend on the filesystem.
+///
+/// Files with more than one hard link get an `nlink` property like libarchive records it. The
+/// digests of a hardlinked file are only computed for the first of its paths.
+/// Digests are computed in parallel after the whole tree was walked.
+#[tracing::instrument(skip(options), fields(root = %root.display()), err)]
+pub fn create(root: &Path, options: &CreateOptions) -> io::Result<Vec<Entry<'static>>> {
+    #[cfg(not(feature = "digests"))]
+    if !options.digests.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "computing digests requires the `digests` feature",
+        ));
+    }
+
+    let mut walk = Walk::default();
+    walk.dir(root, ".", options)?;
+    tracing::info!(entries = walk.entries.len(), "walked tree");
+
+    #[cfg(feature = "digests")]
+    if !options.digests.is_empty() {
+        walk.hash(options)?;
+    }
+
+    Ok(walk.entries)
+}
+
+/// The state of walking a tree.
+#[derive(Default)]
+#[cfg_attr(not(feature = "digests"), allow(dead_code))]
+struct Walk {
+    entries: Vec<Entry<'static>>,
+    /// The inodes of hardlinked files and the index of their first entry.
+    links: HashMap<(u64, u64), usize>,
+    /// The index and the path on disk of every regular file that needs digests.
+    files: Vec<(usize, PathBuf)>,
+    /// The index of every further path of a hardlinked file and of its first entry.
+    copies: Vec<(usize, usize)>,
+}
+
+impl Walk {
+    /// Add entries for all children of the given directory, recursively.
+    fn dir(&mut self, dir: &Path, prefix: &str, options: &CreateOptions) -> io::Result<()> {
+        let mut children = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+        children.sort_by_key(|child| child.file_name());
+
+        for child in children {
+            let path = format!("{prefix}/{}", child.file_name().to_string_lossy());
+            let metadata = child.path().symlink_metadata()?;
+            if let Some(excludes) = &options.excludes {
+                if excludes.is_excluded(&path, metadata.is_dir()) {
+                    tracing::debug!(%path, "excluded");
+                    continue;
+                }
+            }
+
+            let index = self.entries.len();
+            let entry = entry(&child.path(), path.clone(), &metadata, options)?;
+            let is_dir = entry.path_type == Some(PathType::Dir);
+            if entry.path_type == Some(PathType::File) {
+                match inode(&metadata).map(|inode| *self.links.entry(inode).or_insert(index)) {
+                    Some(first) if first != index => self.copies.push((index, first)),
+                    _ => self.files.push((index, child.path())),
+                }
+            }
+            self.entries.push(entry);
+
+            if is_dir {
+                self.dir(&child.path(), &path, options)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Compute the digests of all files on a dedicated thread pool.
+    #[cfg(feature = "digests")]
+    fn hash(&mut self, options: &CreateOptions) -> io::Result<()> {
+        use rayon::prelude::*;
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(options.threads)
+            .build()
+            .map_err(io::Error::other)?;
+        let progress = options.progress.as_deref().unwrap_or(&());
+        let size = |index: usize| self.entries[index].size.unwrap_or(0) as u64;
+        let bytes = self.files.iter().map(|(index, _)| size(*index)).sum();
+        tracing::info!(
+            files = self.files.len(),
+            bytes,
+            threads = pool.current_num_threads(),
+            "hashing files"
+        );
+        progress.start(self.files.len() as u64, bytes);
+        let digests = pool.install(|| {
+            self.files
+                .par_iter()
+                .map(|(index, path)| {
+                    let digests = super::digest::hash_file(path, &options.digests)?;
+                    progress.advance(size(*index));
+                    Ok((*index, digests))
+                })
+                .collect::<io::Result<Vec<_>>>()
+        });
+        progress.finish();
+        let digests = digests?;
+
+        for (index, digests) in digests {
+            let entry = &mut self.entries[index];
+            for (algorithm, digest) in digests {
+                algorithm.set(entry, digest);
+            }
+        }
+        for &(index, first) in &self.copies {
+            let first = self.entries[first].clone();
+            let entry = &mut self.entries[index];
+            entry.md5digest = first.md5digest;
+            entry.sha1digest = first.sha1digest;
+            entry.sha256digest = first.sha256digest;
+        }
+
+        Ok(())
+    }
+}
+
+/// Capture the properties of a single path, except for its digests.
+fn entry(
+    fs_path: &Path,
+    path: String,
+    metadata: &Metadata,
+    options: &CreateOptions,
+) -> io::Result<Entry<'static>> {
+    let file_type = metadata.file_type();
+    let mut entry = Entry::new(path);
+    entry.path_type = Some(if file_type.is_symlink() {
+        PathType::Link
+    } else if file_type.is_dir() {
+        PathType::Dir
+    } else {
+        special_type(metadata).unwrap_or(PathType::File)
+    });
+    if !options.omit_time {
+        let time = metadata.modified().map(Timestamp::from)?;
+        entry.time = Some(match options.source_date_epoch {
+            Some(epoch) if time > Timestamp::new(epoch, 0) => Timestamp::new(epoch, 0),
+            _ => time,
+        });
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        use super::{Gid, Mode, Uid};
+
+        if !options.omit_owner {
+            entry.uid = Some(Uid(metadata.uid()));
+            entry.gid = Some(Gid(metadata.gid()));
+        }
+        entry.mode = Some(Mode::from_bits_truncate(metadata.mode()));
+        if !file_type.is_dir() && metadata.nlink() != 1 {
+            entry.nlink = Some(metadata.nlink() as usize);
+        }
+    }
+
+    match entry.path_type {
+        Some(PathType::File) => entry.size = Some(metadata.len() as usize),
+        Some(PathType::Link) => {
+            let link = fs::read_link(fs_path)?;
+            entry.link = Some(Cow::Owned(link.to_string_lossy().into_owned()));
+        }
+        Some(PathType::Block | PathType::Char) => entry.device = device(metadata),
+        _ => {}
+    }
+
+    Ok(entry)
+}
+
+/// The type of block and character devices, fifos and sockets.
+fn special_type(
+    #[cfg_attr(not(unix), allow(unused_variables))] metadata: &Metadata,
+) -> Option<PathType> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+
+        let file_type = metadata.file_type();
+        if file_type.is_block_device() {
+            return Some(PathType::Block);
+        } else if file_type.is_char_device() {
+            return Some(PathType::Char);
+        } else if file_type.is_fifo() {
+            return Some(PathType::Fifo);
+        } else if file_type.is_socket() {
+            return Some(PathType::Socket);
+        }
+    }
+
+    None
+}
+
+/// The device and inode number of a file that has more than one hard link.
+#[cfg(unix)]
+fn inode(metadata: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+
+    (!metadata.is_dir() && metadata.nlink() > 1).then(|| (metadata.dev(), metadata.ino()))
+}
+
+/// Hard links can't be detected without inode numbers.
+#[cfg(not(unix))]
+fn inode(_metadata: &Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// The device number of a device node.
+#[cfg(target_os = "linux")]
+fn device(metadata: &Metadata) -> Option<super::Device> {
+    use std::os::unix::fs::MetadataExt;
+
+    use super::{Device, DeviceFormat};
+
+    let rdev = metadata.rdev();
+    Some(Device {
+        format: DeviceFormat::Linux,
+        major: (((rdev >> 8) & 0xfff) | ((rdev >> 32) & !0xfff)) as u32,
+        minor: ((rdev & 0xff) | ((rdev >> 12) & !0xff)) as u32,
+    })
+}
+
+/// The encoding of major and minor numbers is only known for Linux.
+#[cfg(not(target_os = "linux"))]
+fn device(_metadata: &Metadata) -> Option<super::Device> {
+    None
+}
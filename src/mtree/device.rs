@@ -0,0 +1,143 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A device number as given by the `device=` and `resdevice=` keywords,
+/// e.g. `device=linux,1,3` for `/dev/null`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Device {
+    pub format: DeviceFormat,
+    pub major: u32,
+    pub minor: u32,
+}
+
+/// The format of a device number, which defines how major and minor numbers are packed.
+/// These are all formats known to mtree(5).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DeviceFormat {
+    Native,
+    Bsd386,
+    Bsd4,
+    BsdOs,
+    FreeBsd,
+    HpUx,
+    Isc,
+    Linux,
+    NetBsd,
+    Osf1,
+    Sco,
+    Solaris,
+    SunOs,
+    Svr3,
+    Svr4,
+    Ultrix,
+}
+
+impl DeviceFormat {
+    /// The name of the format as used in a manifest.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Native => "native",
+            Self::Bsd386 => "386bsd",
+            Self::Bsd4 => "4bsd",
+            Self::BsdOs => "bsdos",
+            Self::FreeBsd => "freebsd",
+            Self::HpUx => "hpux",
+            Self::Isc => "isc",
+            Self::Linux => "linux",
+            Self::NetBsd => "netbsd",
+            Self::Osf1 => "osf1",
+            Self::Sco => "sco",
+            Self::Solaris => "solaris",
+            Self::SunOs => "sunos",
+            Self::Svr3 => "svr3",
+            Self::Svr4 => "svr4",
+            Self::Ultrix => "ultrix",
+        }
+    }
+}
+
+impl FromStr for DeviceFormat {
+    type Err = ParseDeviceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "native" => Self::Native,
+            "386bsd" => Self::Bsd386,
+            "4bsd" => Self::Bsd4,
+            "bsdos" => Self::BsdOs,
+            "freebsd" => Self::FreeBsd,
+            "hpux" => Self::HpUx,
+            "isc" => Self::Isc,
+            "linux" => Self::Linux,
+            "netbsd" => Self::NetBsd,
+            "osf1" => Self::Osf1,
+            "sco" => Self::Sco,
+            "solaris" => Self::Solaris,
+            "sunos" => Self::SunOs,
+            "svr3" => Self::Svr3,
+            "svr4" => Self::Svr4,
+            "ultrix" => Self::Ultrix,
+            _ => return Err(ParseDeviceError::UnknownFormat),
+        })
+    }
+}
+
+/// Formats are serialized by their manifest name, e.g. `386bsd`.
+impl Serialize for DeviceFormat {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for DeviceFormat {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl FromStr for Device {
+    type Err = ParseDeviceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = s.split(',');
+        let (Some(format), Some(major), Some(minor), None) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        else {
+            return Err(ParseDeviceError::InvalidSyntax);
+        };
+
+        Ok(Self {
+            format: format.parse()?,
+            major: major.parse().map_err(|_| ParseDeviceError::InvalidNumber)?,
+            minor: minor.parse().map_err(|_| ParseDeviceError::InvalidNumber)?,
+        })
+    }
+}
+
+impl fmt::Display for Device {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{},{}", self.format.as_str(), self.major, self.minor)
+    }
+}
+
+/// Errors that can occur when parsing a [Device].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseDeviceError {
+    InvalidSyntax,
+    UnknownFormat,
+    InvalidNumber,
+}
+
+impl fmt::Display for ParseDeviceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidSyntax => write!(f, "device must be in the form of `format,major,minor`"),
+            Self::UnknownFormat => write!(f, "unknown device format"),
+            Self::InvalidNumber => write!(f, "device major and minor must be decimal numbers"),
+        }
+    }
+}
+
+impl std::error::Error for ParseDeviceError {}
@@ -0,0 +1,209 @@
+use std::{
+    fmt,
+    io::{self, Write},
+    ops::Range,
+    str::FromStr,
+};
+
+use anyhow::bail;
+use ariadne::{Color, Config, Label, Report, ReportKind, Source};
+use serde::Serialize;
+
+use super::{Diag, Finding, FindingKind, Mismatch, MismatchKind};
+
+/// How severe a diagnostic is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub const ALL: &'static [Severity] = &[Self::Info, Self::Warning, Self::Error];
+}
+
+impl FromStr for Severity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match Self::ALL.iter().find(|severity| severity.to_string() == s) {
+            Some(severity) => Ok(*severity),
+            None => bail!("unknown severity: {s}"),
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        })
+    }
+}
+
+/// A message about a manifest, e.g. a parse error, a lint or a verification mismatch.
+///
+/// Every part of the crate that reports problems can be turned into this type, so that front-ends
+/// only have to render one kind of message and errors and warnings can be collected together.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// A short, stable identifier of the kind of problem, e.g. `unknown-keyword`.
+    pub code: &'static str,
+    pub message: String,
+    /// The byte range of the manifest that the diagnostic refers to, if any.
+    pub span: Option<Range<usize>>,
+    /// Additional explanations or hints.
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            code,
+            message: message.into(),
+            span: None,
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn error(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, code, message)
+    }
+
+    pub fn warning(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, code, message)
+    }
+
+    pub fn with_span(mut self, span: Range<usize>) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    /// Render the diagnostic for a terminal, with the referenced part of the manifest in
+    /// `source` if it has a span. The `name` of the manifest is usually its path.
+    pub fn write<W: Write>(&self, name: &str, source: &str, writer: W) -> io::Result<()> {
+        self.write_with_color(name, source, writer, true)
+    }
+
+    /// Like [Diagnostic::write], but only with colors if `colored` is set, e.g. for files or
+    /// pipes.
+    pub fn write_with_color<W: Write>(
+        &self,
+        name: &str,
+        source: &str,
+        mut writer: W,
+        colored: bool,
+    ) -> io::Result<()> {
+        let Some(span) = &self.span else {
+            writeln!(writer, "{name}: {self}")?;
+            for note in &self.notes {
+                writeln!(writer, "  = note: {note}")?;
+            }
+            return Ok(());
+        };
+
+        let (kind, color) = match self.severity {
+            Severity::Info => (ReportKind::Advice, Color::Blue),
+            Severity::Warning => (ReportKind::Warning, Color::Yellow),
+            Severity::Error => (ReportKind::Error, Color::Red),
+        };
+        let mut report = Report::build(kind, name, span.start)
+            .with_config(Config::default().with_color(colored))
+            .with_code(self.code)
+            .with_message(&self.message)
+            .with_label(
+                Label::new((name, span.clone()))
+                    .with_message(&self.message)
+                    .with_color(color),
+            );
+        for note in &self.notes {
+            report = report.with_note(note);
+        }
+
+        report.finish().write((name, Source::from(source)), writer)
+    }
+
+    /// Render the diagnostic like [Diagnostic::write] into a string without colors, e.g. for
+    /// applications that show errors in a log or a dialog.
+    pub fn render(&self, name: &str, source: &str) -> String {
+        let mut rendered = Vec::new();
+        self.write_with_color(name, source, &mut rendered, false)
+            .expect("writing to a Vec doesn't fail");
+        String::from_utf8_lossy(&rendered).into_owned()
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}]: {}", self.severity, self.code, self.message)?;
+        if let Some(span) = &self.span {
+            write!(f, " at {}..{}", span.start, span.end)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+/// A parse error of a backend.
+impl From<Diag> for Diagnostic {
+    fn from(diag: Diag) -> Self {
+        Self::error("syntax", diag.message).with_span(diag.span)
+    }
+}
+
+impl From<Finding> for Diagnostic {
+    fn from(finding: Finding) -> Self {
+        let code = match finding.kind {
+            FindingKind::WorldWritable => "world-writable",
+            FindingKind::Setuid => "setuid",
+            FindingKind::Setgid => "setgid",
+            FindingKind::SymlinkEscape { .. } => "symlink-escape",
+            FindingKind::ParentComponent => "parent-component",
+            FindingKind::AbsolutePath => "absolute-path",
+            FindingKind::DeviceNode => "device-node",
+        };
+
+        Self::new(
+            finding.severity,
+            code,
+            format!("{}: {}", finding.path, finding.kind),
+        )
+    }
+}
+
+impl From<Mismatch> for Diagnostic {
+    fn from(mismatch: Mismatch) -> Self {
+        let code = match mismatch.kind {
+            MismatchKind::Missing => "missing",
+            MismatchKind::Extra => "extra",
+            MismatchKind::Inaccessible { .. } => "inaccessible",
+            MismatchKind::Type { .. } => "type-mismatch",
+            MismatchKind::Size { .. } => "size-mismatch",
+            MismatchKind::Mode { .. } => "mode-mismatch",
+            MismatchKind::Uid { .. } => "uid-mismatch",
+            MismatchKind::Gid { .. } => "gid-mismatch",
+            MismatchKind::Time { .. } => "time-mismatch",
+            MismatchKind::Link { .. } => "link-mismatch",
+            MismatchKind::Digest { .. } => "digest-mismatch",
+        };
+
+        Self::error(code, mismatch.to_string())
+    }
+}
@@ -0,0 +1,155 @@
+use std::{borrow::Cow, fmt};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Device, Entry, Gid, Mode, MtreeIndex, PathType, Timestamp, Uid};
+
+/// A difference of a single path between two manifests.
+///
+/// As JSON, a change is an object with the `path` and the `kind`, modified paths also have the
+/// `changes` of their properties.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Change<'a> {
+    pub path: Cow<'a, str>,
+    #[serde(flatten)]
+    pub kind: ChangeKind<'a>,
+}
+
+/// How a path differs between two manifests.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase", tag = "kind", content = "changes")]
+pub enum ChangeKind<'a> {
+    /// The path only exists in the new manifest.
+    Added,
+    /// The path only exists in the old manifest.
+    Removed,
+    /// The path exists in both manifests, but some of its properties differ.
+    Modified(Vec<PropertyChange<'a>>),
+}
+
+/// A property that differs between two entries of the same path.
+/// Each variant holds the `(old, new)` value, `None` if the property isn't set.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PropertyChange<'a> {
+    Type(Option<PathType>, Option<PathType>),
+    Uid(Option<Uid>, Option<Uid>),
+    Gid(Option<Gid>, Option<Gid>),
+    Uname(Option<Cow<'a, str>>, Option<Cow<'a, str>>),
+    Gname(Option<Cow<'a, str>>, Option<Cow<'a, str>>),
+    Mode(Option<Mode>, Option<Mode>),
+    Size(Option<usize>, Option<usize>),
+    Nlink(Option<usize>, Option<usize>),
+    Link(Option<Cow<'a, str>>, Option<Cow<'a, str>>),
+    Md5Digest(Option<Cow<'a, str>>, Option<Cow<'a, str>>),
+    Sha1Digest(Option<Cow<'a, str>>, Option<Cow<'a, str>>),
+    Sha256Digest(Option<Cow<'a, str>>, Option<Cow<'a, str>>),
+    Time(Option<Timestamp>, Option<Timestamp>),
+    Device(Option<Device>, Option<Device>),
+    ResDevice(Option<Device>, Option<Device>),
+}
+
+/// Formats the change as `keyword: old -> new`, a property that isn't set is `(none)`.
+impl fmt::Display for PropertyChange<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn show<T: fmt::Display>(value: &Option<T>) -> String {
+            value
+                .as_ref()
+                .map_or_else(|| String::from("(none)"), T::to_string)
+        }
+        let (keyword, old, new) = match self {
+            Self::Type(old, new) => ("type", show(old), show(new)),
+            Self::Uid(old, new) => ("uid", show(old), show(new)),
+            Self::Gid(old, new) => ("gid", show(old), show(new)),
+            Self::Uname(old, new) => ("uname", show(old), show(new)),
+            Self::Gname(old, new) => ("gname", show(old), show(new)),
+            Self::Mode(old, new) => ("mode", show(old), show(new)),
+            Self::Size(old, new) => ("size", show(old), show(new)),
+            Self::Nlink(old, new) => ("nlink", show(old), show(new)),
+            Self::Link(old, new) => ("link", show(old), show(new)),
+            Self::Md5Digest(old, new) => ("md5digest", show(old), show(new)),
+            Self::Sha1Digest(old, new) => ("sha1digest", show(old), show(new)),
+            Self::Sha256Digest(old, new) => ("sha256digest", show(old), show(new)),
+            Self::Time(old, new) => ("time", show(old), show(new)),
+            Self::Device(old, new) => ("device", show(old), show(new)),
+            Self::ResDevice(old, new) => ("resdevice", show(old), show(new)),
+        };
+
+        write!(f, "{keyword}: {old} -> {new}")
+    }
+}
+
+/// Compare two manifests, e.g. two versions of the same package.
+///
+/// The changes are sorted by path. Paths that are identical in both manifests aren't reported.
+pub fn diff<'a>(old: &[Entry<'a>], new: &[Entry<'a>]) -> Vec<Change<'a>> {
+    let old = MtreeIndex::new(old.iter().cloned());
+    let new = MtreeIndex::new(new.iter().cloned());
+
+    let mut changes = Vec::new();
+    let mut old_entries = old.iter().peekable();
+    let mut new_entries = new.iter().peekable();
+    loop {
+        let (path, kind) = match (old_entries.peek(), new_entries.peek()) {
+            (None, None) => break,
+            (Some(old), None) => (&old.path, ChangeKind::Removed),
+            (None, Some(new)) => (&new.path, ChangeKind::Added),
+            (Some(old), Some(new)) if old.path < new.path => (&old.path, ChangeKind::Removed),
+            (Some(old), Some(new)) if old.path > new.path => (&new.path, ChangeKind::Added),
+            (Some(old), Some(new)) => (&old.path, ChangeKind::Modified(compare(old, new))),
+        };
+        let path = path.clone();
+
+        match &kind {
+            ChangeKind::Removed => {
+                old_entries.next();
+            }
+            ChangeKind::Added => {
+                new_entries.next();
+            }
+            ChangeKind::Modified(properties) => {
+                old_entries.next();
+                new_entries.next();
+                if properties.is_empty() {
+                    continue;
+                }
+            }
+        }
+        changes.push(Change { path, kind });
+    }
+
+    changes
+}
+
+/// Compare all properties of two entries.
+fn compare<'a>(old: &Entry<'a>, new: &Entry<'a>) -> Vec<PropertyChange<'a>> {
+    let mut changes = Vec::new();
+    macro_rules! compare {
+        ($($field:ident => $variant:ident),* $(,)?) => {
+            $(
+                if old.$field != new.$field {
+                    changes.push(PropertyChange::$variant(old.$field.clone(), new.$field.clone()));
+                }
+            )*
+        };
+    }
+    compare!(
+        path_type => Type,
+        uid => Uid,
+        gid => Gid,
+        uname => Uname,
+        gname => Gname,
+        mode => Mode,
+        size => Size,
+        nlink => Nlink,
+        link => Link,
+        md5digest => Md5Digest,
+        sha1digest => Sha1Digest,
+        sha256digest => Sha256Digest,
+        time => Time,
+        device => Device,
+        resdevice => ResDevice,
+    );
+
+    changes
+}
@@ -0,0 +1,205 @@
+use std::{borrow::Cow, fmt, str::FromStr};
+
+use anyhow::bail;
+
+use serde::{Deserialize, Serialize};
+
+use super::Entry;
+
+/// The digest algorithms that can be recorded for a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DigestAlgorithm {
+    Md5,
+    Sha1,
+    Sha256,
+}
+
+impl DigestAlgorithm {
+    pub const ALL: &'static [DigestAlgorithm] = &[Self::Md5, Self::Sha1, Self::Sha256];
+
+    /// The keyword of this digest in a manifest.
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            Self::Md5 => "md5digest",
+            Self::Sha1 => "sha1digest",
+            Self::Sha256 => "sha256digest",
+        }
+    }
+
+    /// The hex digest that the manifest records for this algorithm, if any.
+    pub fn expected<'e>(&self, entry: &'e Entry) -> Option<&'e str> {
+        match self {
+            Self::Md5 => entry.md5digest.as_deref(),
+            Self::Sha1 => entry.sha1digest.as_deref(),
+            Self::Sha256 => entry.sha256digest.as_deref(),
+        }
+    }
+
+    /// Record a hex digest for this algorithm in an entry, replacing the previous one.
+    pub fn set(&self, entry: &mut Entry, digest: String) {
+        let digest = Some(Cow::Owned(digest));
+        match self {
+            Self::Md5 => entry.md5digest = digest,
+            Self::Sha1 => entry.sha1digest = digest,
+            Self::Sha256 => entry.sha256digest = digest,
+        }
+    }
+}
+
+impl fmt::Display for DigestAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Md5 => "md5",
+            Self::Sha1 => "sha1",
+            Self::Sha256 => "sha256",
+        })
+    }
+}
+
+impl FromStr for DigestAlgorithm {
+    type Err = anyhow::Error;
+
+    /// Parse the name of an algorithm, e.g. `sha256`.
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match Self::ALL
+            .iter()
+            .find(|algorithm| algorithm.to_string() == s)
+        {
+            Some(algorithm) => Ok(*algorithm),
+            None => bail!("unknown digest algorithm: {s}"),
+        }
+    }
+}
+
+/// Compute the hex digests of a file for all of the given algorithms in a single pass.
+#[cfg(feature = "digests")]
+pub fn hash_file(
+    path: &std::path::Path,
+    algorithms: &[DigestAlgorithm],
+) -> std::io::Result<Vec<(DigestAlgorithm, String)>> {
+    hash_reader(std::fs::File::open(path)?, algorithms)
+}
+
+/// Compute the hex digests of all data of a reader for all of the given algorithms.
+#[cfg(feature = "digests")]
+pub fn hash_reader<R: std::io::Read>(
+    mut reader: R,
+    algorithms: &[DigestAlgorithm],
+) -> std::io::Result<Vec<(DigestAlgorithm, String)>> {
+    let mut hasher = Hasher::new(algorithms);
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(hasher.finalize())
+}
+
+/// Computes multiple digests of the same data at once.
+#[cfg(feature = "digests")]
+pub struct Hasher {
+    md5: Option<md5::Md5>,
+    sha1: Option<sha1::Sha1>,
+    sha256: Option<sha2::Sha256>,
+}
+
+#[cfg(feature = "digests")]
+impl Hasher {
+    pub fn new(algorithms: &[DigestAlgorithm]) -> Self {
+        use sha2::Digest;
+
+        Self {
+            md5: algorithms
+                .contains(&DigestAlgorithm::Md5)
+                .then(md5::Md5::new),
+            sha1: algorithms
+                .contains(&DigestAlgorithm::Sha1)
+                .then(sha1::Sha1::new),
+            sha256: algorithms
+                .contains(&DigestAlgorithm::Sha256)
+                .then(sha2::Sha256::new),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        use sha2::Digest;
+
+        self.md5.iter_mut().for_each(|hasher| hasher.update(data));
+        self.sha1.iter_mut().for_each(|hasher| hasher.update(data));
+        self.sha256
+            .iter_mut()
+            .for_each(|hasher| hasher.update(data));
+    }
+
+    /// The hex digests of all data so far.
+    pub fn finalize(self) -> Vec<(DigestAlgorithm, String)> {
+        use sha2::Digest;
+
+        let mut digests = Vec::new();
+        if let Some(hasher) = self.md5 {
+            digests.push((DigestAlgorithm::Md5, to_hex(&hasher.finalize())));
+        }
+        if let Some(hasher) = self.sha1 {
+            digests.push((DigestAlgorithm::Sha1, to_hex(&hasher.finalize())));
+        }
+        if let Some(hasher) = self.sha256 {
+            digests.push((DigestAlgorithm::Sha256, to_hex(&hasher.finalize())));
+        }
+
+        digests
+    }
+}
+
+/// Encode bytes as lowercase hex.
+pub fn to_hex(bytes: &[u8]) -> String {
+    #[cfg(feature = "simd-hex")]
+    return faster_hex::hex_string(bytes);
+
+    #[cfg(not(feature = "simd-hex"))]
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Decode a hex digest into its bytes, e.g. `from_hex::<32>` for a sha256 digest.
+/// Both lowercase and uppercase digits are accepted.
+///
+/// With the `simd-hex` feature, the digits are validated and decoded with SIMD instructions.
+pub fn from_hex<const N: usize>(hex: &str) -> Result<[u8; N], FromHexError> {
+    if hex.len() != N * 2 {
+        return Err(FromHexError::InvalidLength);
+    }
+    let mut bytes = [0; N];
+
+    #[cfg(feature = "simd-hex")]
+    faster_hex::hex_decode(hex.as_bytes(), &mut bytes).map_err(|_| FromHexError::InvalidDigit)?;
+
+    #[cfg(not(feature = "simd-hex"))]
+    for (byte, digits) in bytes.iter_mut().zip(hex.as_bytes().chunks_exact(2)) {
+        let digit = |c: u8| char::from(c).to_digit(16).ok_or(FromHexError::InvalidDigit);
+        *byte = (digit(digits[0])? << 4 | digit(digits[1])?) as u8;
+    }
+
+    Ok(bytes)
+}
+
+/// Errors that can occur when decoding a hex digest with [from_hex].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FromHexError {
+    InvalidLength,
+    InvalidDigit,
+}
+
+impl fmt::Display for FromHexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidLength => write!(f, "digest has the wrong length"),
+            Self::InvalidDigit => write!(f, "digest contains a non-hex digit"),
+        }
+    }
+}
+
+impl std::error::Error for FromHexError {}
@@ -0,0 +1,68 @@
+use std::{cmp::Reverse, collections::BTreeMap};
+
+use serde::Serialize;
+
+use super::{digest::from_hex, Entry, PathType};
+
+/// A group of files that all have the same content.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Duplicate<'e> {
+    /// The shared sha256 digest.
+    pub sha256digest: &'e str,
+    /// The size of a single copy, if the manifest records it.
+    pub size: Option<usize>,
+    /// The paths of all copies, in the order they were given.
+    pub paths: Vec<&'e str>,
+}
+
+impl Duplicate<'_> {
+    /// The number of copies.
+    pub fn count(&self) -> usize {
+        self.paths.len()
+    }
+
+    /// The bytes that could be saved by keeping only one copy.
+    pub fn wasted_bytes(&self) -> usize {
+        self.size.unwrap_or_default() * (self.count() - 1)
+    }
+}
+
+/// Find files with duplicated contents by grouping them by their sha256 digest.
+///
+/// Entries of several manifests can be chained to look for duplicates across a repository.
+/// Digests are compared by their bytes, so the case of the hex digits doesn't matter.
+/// Files without a valid sha256 digest are ignored. Note that hardlinks show up as duplicates too,
+/// since a manifest can't tell them apart from copies.
+///
+/// The groups are sorted by the wasted bytes, the biggest first.
+pub fn duplicates<'e, 'a: 'e>(
+    entries: impl IntoIterator<Item = &'e Entry<'a>>,
+) -> Vec<Duplicate<'e>> {
+    let mut groups: BTreeMap<[u8; 32], Duplicate<'e>> = BTreeMap::new();
+    for entry in entries {
+        if !matches!(entry.path_type, None | Some(PathType::File)) {
+            continue;
+        }
+        let Some(digest) = entry.sha256digest.as_deref() else {
+            continue;
+        };
+        let Ok(bytes) = from_hex::<32>(digest) else {
+            continue;
+        };
+
+        let group = groups.entry(bytes).or_insert_with(|| Duplicate {
+            sha256digest: digest,
+            size: entry.size,
+            paths: Vec::new(),
+        });
+        group.paths.push(&entry.path);
+    }
+
+    let mut duplicates: Vec<_> = groups
+        .into_values()
+        .filter(|group| group.count() > 1)
+        .collect();
+    duplicates.sort_by_key(|group| Reverse(group.wasted_bytes()));
+
+    duplicates
+}
@@ -0,0 +1,85 @@
+use std::{fmt, io};
+
+/// The text encoding of a file, as told by its byte order mark or its first bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// UTF-8 without a byte order mark, the encoding of every file that pacman writes.
+    Utf8,
+    /// UTF-8 with a byte order mark, as some Windows editors save files.
+    Utf8Bom,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl Encoding {
+    /// Detect the encoding from the first bytes of a file.
+    ///
+    /// UTF-16 without a byte order mark is recognized if the file starts with an ASCII
+    /// character, which every manifest and `.PKGINFO` does.
+    pub fn detect(bytes: &[u8]) -> Self {
+        match bytes {
+            [0xef, 0xbb, 0xbf, ..] => Self::Utf8Bom,
+            [0xff, 0xfe, ..] => Self::Utf16Le,
+            [0xfe, 0xff, ..] => Self::Utf16Be,
+            [1..=0x7f, 0, ..] => Self::Utf16Le,
+            [0, 1..=0x7f, ..] => Self::Utf16Be,
+            _ => Self::Utf8,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Utf8 => "UTF-8",
+            Self::Utf8Bom => "UTF-8 with a byte order mark",
+            Self::Utf16Le => "UTF-16LE",
+            Self::Utf16Be => "UTF-16BE",
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Decode a file into a string, whatever [Encoding] it has. The byte order mark is dropped.
+///
+/// Invalid UTF-8 or UTF-16 is an [io::ErrorKind::InvalidData] error.
+pub fn decode(bytes: Vec<u8>) -> io::Result<String> {
+    let encoding = Encoding::detect(&bytes);
+    let units = |bytes: &[u8], unit: fn([u8; 2]) -> u16| {
+        if !bytes.len().is_multiple_of(2) {
+            return Err(invalid(format!(
+                "{encoding} input with an odd number of bytes"
+            )));
+        }
+        let units = bytes.chunks_exact(2).map(|pair| unit([pair[0], pair[1]]));
+        char::decode_utf16(units)
+            .collect::<Result<String, _>>()
+            .map_err(|err| invalid(format!("invalid {encoding}: {err}")))
+    };
+
+    let mut content = match encoding {
+        Encoding::Utf8 | Encoding::Utf8Bom => String::from_utf8(bytes).map_err(invalid)?,
+        Encoding::Utf16Le => units(&bytes, u16::from_le_bytes)?,
+        Encoding::Utf16Be => units(&bytes, u16::from_be_bytes)?,
+    };
+    if content.starts_with('\u{feff}') {
+        content.remove(0);
+    }
+
+    Ok(content)
+}
+
+/// Like [decode], but only accept plain UTF-8 without a byte order mark.
+pub fn decode_strict(bytes: Vec<u8>) -> io::Result<String> {
+    match Encoding::detect(&bytes) {
+        Encoding::Utf8 => String::from_utf8(bytes).map_err(invalid),
+        encoding => Err(invalid(format!("expected plain UTF-8, found {encoding}"))),
+    }
+}
+
+fn invalid<E: Into<Box<dyn std::error::Error + Send + Sync>>>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
@@ -0,0 +1,501 @@
+use std::{borrow::Cow, cmp::Ordering, collections::HashMap, fmt, str::FromStr};
+
+use chumsky::Parser;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    vis, Device, Diagnostic, Gid, Keyword, Mode, PathType, Property, Statement, Timestamp, Uid,
+};
+
+/// The metadata files at the root of an ALPM package, which pacman doesn't install.
+pub const METADATA_FILES: &[&str] = &[".PKGINFO", ".MTREE", ".BUILDINFO", ".INSTALL", ".CHANGELOG"];
+
+/// A single path of a manifest with all of its properties resolved.
+///
+/// In contrast to a [Statement::Path], the defaults of all preceding `/set` and `/unset`
+/// commands are already applied, so this is the full picture of what a path should look like.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry<'a> {
+    #[serde(borrow)]
+    pub path: Cow<'a, str>,
+    #[serde(rename = "type")]
+    pub path_type: Option<PathType>,
+    pub uid: Option<Uid>,
+    pub gid: Option<Gid>,
+    #[serde(borrow)]
+    pub uname: Option<Cow<'a, str>>,
+    #[serde(borrow)]
+    pub gname: Option<Cow<'a, str>>,
+    pub mode: Option<Mode>,
+    pub size: Option<usize>,
+    pub nlink: Option<usize>,
+    #[serde(borrow)]
+    pub link: Option<Cow<'a, str>>,
+    #[serde(borrow)]
+    pub md5digest: Option<Cow<'a, str>>,
+    #[serde(borrow)]
+    pub sha1digest: Option<Cow<'a, str>>,
+    #[serde(borrow)]
+    pub sha256digest: Option<Cow<'a, str>>,
+    pub time: Option<Timestamp>,
+    pub device: Option<Device>,
+    pub resdevice: Option<Device>,
+}
+
+impl<'a> Entry<'a> {
+    /// Create an entry for the given path without any properties.
+    pub fn new(path: impl Into<Cow<'a, str>>) -> Self {
+        Self {
+            path: path.into(),
+            path_type: None,
+            uid: None,
+            gid: None,
+            uname: None,
+            gname: None,
+            mode: None,
+            size: None,
+            nlink: None,
+            link: None,
+            md5digest: None,
+            sha1digest: None,
+            sha256digest: None,
+            time: None,
+            device: None,
+            resdevice: None,
+        }
+    }
+
+    /// Set a single property, overwriting any previous value.
+    pub fn apply(&mut self, property: &Property<'a>) {
+        match property {
+            Property::Uid(uid) => self.uid = Some(*uid),
+            Property::Gid(gid) => self.gid = Some(*gid),
+            Property::Uname(uname) => self.uname = Some(uname.clone()),
+            Property::Gname(gname) => self.gname = Some(gname.clone()),
+            Property::Mode(mode) => self.mode = Some(*mode),
+            Property::Type(path_type) => self.path_type = Some(*path_type),
+            Property::Size(size) => self.size = Some(*size),
+            Property::Nlink(nlink) => self.nlink = Some(*nlink),
+            Property::Link(link) => self.link = Some(link.clone()),
+            Property::Md5Digest(digest) => self.md5digest = Some(Cow::Borrowed(digest)),
+            Property::Sha1Digest(digest) => self.sha1digest = Some(Cow::Borrowed(digest)),
+            Property::Sha256Digest(digest) => self.sha256digest = Some(Cow::Borrowed(digest)),
+            Property::Time(time) => self.time = Some(*time),
+            Property::Device(device) => self.device = Some(*device),
+            Property::ResDevice(device) => self.resdevice = Some(*device),
+        }
+    }
+
+    /// Whether this is one of the [METADATA_FILES] of a package, like `./.PKGINFO`, instead of
+    /// a file that is installed. Other dotfiles at the root are installed.
+    pub fn is_metadata(&self) -> bool {
+        let path = self.path.strip_prefix("./").unwrap_or(&self.path);
+        METADATA_FILES.contains(&path)
+    }
+
+    /// Turn this entry into one that doesn't borrow from the input anymore.
+    pub fn into_owned(self) -> Entry<'static> {
+        Entry {
+            path: Cow::Owned(self.path.into_owned()),
+            path_type: self.path_type,
+            uid: self.uid,
+            gid: self.gid,
+            uname: self.uname.map(|s| Cow::Owned(s.into_owned())),
+            gname: self.gname.map(|s| Cow::Owned(s.into_owned())),
+            mode: self.mode,
+            size: self.size,
+            nlink: self.nlink,
+            link: self.link.map(|s| Cow::Owned(s.into_owned())),
+            md5digest: self.md5digest.map(|s| Cow::Owned(s.into_owned())),
+            sha1digest: self.sha1digest.map(|s| Cow::Owned(s.into_owned())),
+            sha256digest: self.sha256digest.map(|s| Cow::Owned(s.into_owned())),
+            time: self.time,
+            device: self.device,
+            resdevice: self.resdevice,
+        }
+    }
+}
+
+/// Entries are ordered by their path in [canonical order](compare_paths) first and then by
+/// their properties, so that sorting is deterministic even with duplicate paths.
+impl Ord for Entry<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let properties = |e: &Self| (e.path_type, e.uid, e.gid, e.mode, e.size, e.nlink, e.time);
+        let devices = |e: &Self| (e.device, e.resdevice);
+
+        compare_paths(&self.path, &other.path)
+            .then_with(|| properties(self).cmp(&properties(other)))
+            .then_with(|| {
+                (&self.uname, &self.gname, &self.link).cmp(&(
+                    &other.uname,
+                    &other.gname,
+                    &other.link,
+                ))
+            })
+            .then_with(|| {
+                (&self.md5digest, &self.sha1digest, &self.sha256digest).cmp(&(
+                    &other.md5digest,
+                    &other.sha1digest,
+                    &other.sha256digest,
+                ))
+            })
+            .then_with(|| devices(self).cmp(&devices(other)))
+    }
+}
+
+impl PartialOrd for Entry<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Compare two manifest paths in the order libarchive writes them: component by component,
+/// each compared byte-wise. A directory comes right before its contents, e.g.
+/// `./a` < `./a/b` < `./a b`, while a plain byte-wise comparison would put `./a b` first.
+pub fn compare_paths(a: &str, b: &str) -> Ordering {
+    a.split('/').cmp(b.split('/'))
+}
+
+/// Methods for lists of entries.
+pub trait Entries {
+    /// Sort the entries by path in [canonical order](compare_paths).
+    /// The sort is stable, so entries of the same path stay in manifest order.
+    fn sort_canonical(&mut self);
+
+    /// Move the paths below the directory `from` to `to`, e.g. from `./build/usr` to `./usr`
+    /// when files were staged in another directory than the one they are installed to.
+    ///
+    /// The prefixes are decoded paths with or without a leading `./`, and only match whole
+    /// components, so `./usr/lib` doesn't move `./usr/lib32`. Paths are kept decoded and the
+    /// [writer](super::writer) escapes them again, whatever characters the new prefix has.
+    /// Link targets, missing parent directories and the order of the entries aren't touched.
+    /// Returns the number of entries that were moved.
+    fn rebase(&mut self, from: &str, to: &str) -> usize;
+}
+
+impl Entries for [Entry<'_>] {
+    fn sort_canonical(&mut self) {
+        self.sort_by(|a, b| compare_paths(&a.path, &b.path));
+    }
+
+    fn rebase(&mut self, from: &str, to: &str) -> usize {
+        let (from, to) = (relative(from), relative(to));
+        let mut moved = 0;
+        for entry in self {
+            if let Some(path) = rebase_path(&entry.path, from, to) {
+                entry.path = Cow::Owned(path);
+                moved += 1;
+            }
+        }
+
+        moved
+    }
+}
+
+/// A path without its leading `./` and trailing slashes, `.` is the empty path.
+fn relative(path: &str) -> &str {
+    let path = path.trim_end_matches('/');
+    match path.strip_prefix("./") {
+        Some(path) => path,
+        None if path == "." => "",
+        None => path,
+    }
+}
+
+/// The path with the prefix `from` replaced by `to`, if it's below `from`.
+fn rebase_path(path: &str, from: &str, to: &str) -> Option<String> {
+    let dot = if path == "." || path.starts_with("./") {
+        "./"
+    } else {
+        ""
+    };
+    let rest = relative(path);
+    let tail = if from.is_empty() {
+        rest
+    } else if rest == from {
+        ""
+    } else {
+        rest.strip_prefix(from)?.strip_prefix('/')?
+    };
+
+    Some(match (to, tail) {
+        ("", "") => ".".to_string(),
+        ("", tail) | (tail, "") => format!("{dot}{tail}"),
+        (to, tail) => format!("{dot}{to}/{tail}"),
+    })
+}
+
+/// The defaults that are currently active due to `/set` and `/unset` commands.
+///
+/// Any property can be a default, they are kept as the properties of an entry without a path.
+#[derive(Debug, Clone)]
+struct Defaults<'a>(Entry<'a>);
+
+impl Default for Defaults<'_> {
+    fn default() -> Self {
+        Self(Entry::new(""))
+    }
+}
+
+impl<'a> Defaults<'a> {
+    fn set(&mut self, property: &Property<'a>) {
+        self.0.apply(property);
+    }
+
+    fn unset(&mut self, keyword: Keyword) {
+        let defaults = &mut self.0;
+        match keyword {
+            Keyword::Uid => defaults.uid = None,
+            Keyword::Gid => defaults.gid = None,
+            Keyword::Uname => defaults.uname = None,
+            Keyword::Gname => defaults.gname = None,
+            Keyword::Mode => defaults.mode = None,
+            Keyword::Type => defaults.path_type = None,
+            Keyword::Size => defaults.size = None,
+            Keyword::Nlink => defaults.nlink = None,
+            Keyword::Link => defaults.link = None,
+            Keyword::Md5Digest => defaults.md5digest = None,
+            Keyword::Sha1Digest => defaults.sha1digest = None,
+            Keyword::Sha256Digest => defaults.sha256digest = None,
+            Keyword::Time => defaults.time = None,
+            Keyword::Device => defaults.device = None,
+            Keyword::ResDevice => defaults.resdevice = None,
+        }
+    }
+
+    /// Create a new entry for a path that starts out with the current defaults.
+    fn entry(&self, path: Cow<'a, str>) -> Entry<'a> {
+        Entry {
+            path,
+            ..self.0.clone()
+        }
+    }
+}
+
+/// Resolve parsed statements into entries by applying the active `/set` defaults to each path.
+/// Properties given on the path line itself always take precedence over the defaults.
+/// A path that shows up multiple times results in multiple entries, see [resolve_with].
+#[tracing::instrument(skip_all, fields(statements = statements.len()))]
+pub fn resolve<'a>(statements: &[Statement<'a>]) -> Vec<Entry<'a>> {
+    let mut defaults = Defaults::default();
+    let mut entries = Vec::new();
+
+    for statement in statements {
+        match statement {
+            Statement::Init => {}
+            Statement::Set(properties) => properties.iter().for_each(|p| defaults.set(p)),
+            Statement::Unset(keywords) => keywords.iter().for_each(|k| defaults.unset(*k)),
+            Statement::Path { path, properties } => {
+                let mut entry = defaults.entry(path.clone());
+                properties.iter().for_each(|p| entry.apply(p));
+                entries.push(entry);
+            }
+        }
+    }
+
+    entries
+}
+
+/// What to do if the same path shows up more than once in a manifest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicatePolicy {
+    /// Abort the resolution with an error.
+    Error,
+    /// Keep the first entry and ignore the later lines.
+    FirstWins,
+    /// Apply the properties of the later lines on top of the first entry, like mtree(8) does.
+    /// The merged entry stays at the position of the first line.
+    #[default]
+    LastWins,
+}
+
+impl DuplicatePolicy {
+    pub const ALL: &'static [DuplicatePolicy] = &[Self::Error, Self::FirstWins, Self::LastWins];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::FirstWins => "first-wins",
+            Self::LastWins => "last-wins",
+        }
+    }
+
+    /// The error or warning about a duplicate path, with what the policy does about it.
+    fn duplicate(&self, path: &str) -> Diagnostic {
+        let message = format!("duplicate path `{path}`");
+        match self {
+            Self::Error => Diagnostic::error("duplicate-path", message),
+            Self::FirstWins => Diagnostic::warning("duplicate-path", message)
+                .with_note("the later line is ignored"),
+            Self::LastWins => Diagnostic::warning("duplicate-path", message)
+                .with_note("the properties of the later line are merged into the first"),
+        }
+    }
+}
+
+impl fmt::Display for DuplicatePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for DuplicatePolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match Self::ALL.iter().find(|policy| policy.as_str() == s) {
+            Some(policy) => Ok(*policy),
+            None => anyhow::bail!("unknown duplicate policy: {s}"),
+        }
+    }
+}
+
+/// The entries of a manifest resolved with [resolve_with].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resolved<'a> {
+    pub entries: Vec<Entry<'a>>,
+    /// A warning for every duplicate path that was resolved by the policy.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Like [resolve], but with only one entry per path, as decided by the policy.
+#[tracing::instrument(skip_all, fields(statements = statements.len(), ?policy))]
+pub fn resolve_with<'a>(
+    statements: &[Statement<'a>],
+    policy: DuplicatePolicy,
+) -> Result<Resolved<'a>, Diagnostic> {
+    let mut defaults = Defaults::default();
+    let mut entries: Vec<Entry<'a>> = Vec::new();
+    let mut indices = HashMap::new();
+    let mut diagnostics = Vec::new();
+
+    for statement in statements {
+        let (path, properties) = match statement {
+            Statement::Init => continue,
+            Statement::Set(properties) => {
+                properties.iter().for_each(|p| defaults.set(p));
+                continue;
+            }
+            Statement::Unset(keywords) => {
+                keywords.iter().for_each(|k| defaults.unset(*k));
+                continue;
+            }
+            Statement::Path { path, properties } => (path, properties),
+        };
+
+        let Some(&index) = indices.get(path) else {
+            let mut entry = defaults.entry(path.clone());
+            properties.iter().for_each(|p| entry.apply(p));
+            indices.insert(path.clone(), entries.len());
+            entries.push(entry);
+            continue;
+        };
+
+        tracing::debug!(%path, "duplicate path");
+        let diagnostic = policy.duplicate(path);
+        match policy {
+            DuplicatePolicy::Error => return Err(diagnostic),
+            DuplicatePolicy::FirstWins => {}
+            DuplicatePolicy::LastWins => {
+                let entry = &mut entries[index];
+                properties.iter().for_each(|p| entry.apply(p));
+            }
+        }
+        diagnostics.push(diagnostic);
+    }
+
+    Ok(Resolved {
+        entries,
+        diagnostics,
+    })
+}
+
+/// Look up the entry of a single path without parsing the whole manifest.
+///
+/// Only the `/set` and `/unset` lines and the line of the requested path are parsed, everything
+/// else is skipped and the search stops at the first line of the path. This is much cheaper
+/// than [resolve] if a tool only needs one entry from a huge manifest.
+/// Returns `None` if the path isn't in the manifest. A `/set` or `/unset` line before it, or
+/// the line of the path itself, that doesn't parse is an error, since the entry would be wrong.
+///
+/// A later line of the same path is ignored, like [DuplicatePolicy::FirstWins] does, see
+/// [find_entry_with] for the other policies.
+pub fn find_entry<'a>(input: &'a str, path: &str) -> Result<Option<Entry<'a>>, Diagnostic> {
+    Ok(find_entry_with(input, path, DuplicatePolicy::FirstWins)?
+        .entries
+        .pop())
+}
+
+/// Like [find_entry], but resolve a path that shows up more than once with the policy, like
+/// [resolve_with] does. The entries have the entry of the path, or none if it isn't in the
+/// manifest.
+///
+/// With [DuplicatePolicy::FirstWins] the search stops at the first line of the path, so later
+/// lines are neither parsed nor reported. The other policies have to look for the path in the
+/// rest of the manifest, but still only parse its lines.
+pub fn find_entry_with<'a>(
+    input: &'a str,
+    path: &str,
+    policy: DuplicatePolicy,
+) -> Result<Resolved<'a>, Diagnostic> {
+    let parser = super::parser();
+    // Spans are relative to the slice, diagnostics refer to the whole input.
+    let start = |slice: &str| slice.as_ptr() as usize - input.as_ptr() as usize;
+    let parse = |line: &'a str| {
+        parser.parse(line).into_result().map_err(|errs| {
+            let span = errs[0].span().into_range();
+            Diagnostic::error("syntax", errs[0].to_string())
+                .with_span(start(line) + span.start..start(line) + span.end)
+        })
+    };
+    let mut defaults = Defaults::default();
+    let mut found: Option<Entry<'a>> = None;
+    let mut diagnostics = Vec::new();
+
+    for line in input.lines().map(str::trim) {
+        let head = line.split([' ', '\t']).next().unwrap_or_default();
+        if found.is_none() && (head == "/set" || head == "/unset") {
+            for statement in parse(line)? {
+                match statement {
+                    Statement::Set(properties) => properties.iter().for_each(|p| defaults.set(p)),
+                    Statement::Unset(keywords) => keywords.iter().for_each(|k| defaults.unset(*k)),
+                    _ => {}
+                }
+            }
+            continue;
+        }
+        if vis::decode(head) != path {
+            continue;
+        }
+
+        for statement in parse(line)? {
+            let Statement::Path { path, properties } = statement else {
+                continue;
+            };
+            let Some(entry) = &mut found else {
+                let mut entry = defaults.entry(path);
+                properties.iter().for_each(|p| entry.apply(p));
+                found = Some(entry);
+                continue;
+            };
+
+            let diagnostic = policy
+                .duplicate(&path)
+                .with_span(start(head)..start(head) + head.len());
+            match policy {
+                DuplicatePolicy::Error => return Err(diagnostic),
+                DuplicatePolicy::FirstWins => {}
+                DuplicatePolicy::LastWins => properties.iter().for_each(|p| entry.apply(p)),
+            }
+            diagnostics.push(diagnostic);
+        }
+        if found.is_some() && policy == DuplicatePolicy::FirstWins {
+            break;
+        }
+    }
+
+    Ok(Resolved {
+        entries: found.into_iter().collect(),
+        diagnostics,
+    })
+}
@@ -0,0 +1,66 @@
+use std::path::Path;
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+use super::{Entry, PathType};
+
+/// A set of gitignore-style patterns that exclude paths, e.g. `.git`, `*.pyc` or `*~`.
+///
+/// Patterns are matched against the manifest paths without their leading `./`.
+/// Like in a `.gitignore`, `!pattern` includes a path again and excluding a directory also
+/// excludes everything below it.
+#[derive(Debug, Clone)]
+pub struct Excludes {
+    patterns: Gitignore,
+}
+
+impl Excludes {
+    /// Build the exclude set from the given patterns, one per line.
+    pub fn new<'p>(patterns: impl IntoIterator<Item = &'p str>) -> Result<Self, ignore::Error> {
+        let mut builder = GitignoreBuilder::new("");
+        for pattern in patterns {
+            builder.add_line(None, pattern)?;
+        }
+
+        Ok(Self {
+            patterns: builder.build()?,
+        })
+    }
+
+    /// Load the patterns from a file in the `.gitignore` format.
+    pub fn from_file(path: &Path) -> Result<Self, ignore::Error> {
+        let mut builder = GitignoreBuilder::new("");
+        if let Some(err) = builder.add(path) {
+            return Err(err);
+        }
+
+        Ok(Self {
+            patterns: builder.build()?,
+        })
+    }
+
+    /// Check whether a manifest path (e.g. `./usr/lib/foo.pyc`) is excluded.
+    pub fn is_excluded(&self, path: &str, is_dir: bool) -> bool {
+        let path = path.strip_prefix("./").unwrap_or(path);
+        if path.is_empty() || path == "." {
+            return false;
+        }
+
+        self.patterns
+            .matched_path_or_any_parents(path, is_dir)
+            .is_ignore()
+    }
+
+    /// Check whether an entry is excluded.
+    pub fn excludes(&self, entry: &Entry) -> bool {
+        self.is_excluded(&entry.path, entry.path_type == Some(PathType::Dir))
+    }
+
+    /// Iterate over all entries that aren't excluded.
+    pub fn filter<'e, 'a: 'e>(
+        &'e self,
+        entries: impl IntoIterator<Item = &'e Entry<'a>> + 'e,
+    ) -> impl Iterator<Item = &'e Entry<'a>> + 'e {
+        entries.into_iter().filter(|entry| !self.excludes(entry))
+    }
+}
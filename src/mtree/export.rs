@@ -0,0 +1,175 @@
+use std::{fmt, io::Write, str::FromStr};
+
+use anyhow::{bail, Result};
+
+use super::{Entry, PathType};
+
+/// A column of the CSV export, each one corresponds to a field of an [Entry].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Path,
+    Type,
+    Uid,
+    Gid,
+    Uname,
+    Gname,
+    Mode,
+    Size,
+    Nlink,
+    Link,
+    Md5Digest,
+    Sha1Digest,
+    Sha256Digest,
+    Time,
+    Device,
+    ResDevice,
+}
+
+impl Column {
+    pub const ALL: &'static [Column] = &[
+        Self::Path,
+        Self::Type,
+        Self::Uid,
+        Self::Gid,
+        Self::Uname,
+        Self::Gname,
+        Self::Mode,
+        Self::Size,
+        Self::Nlink,
+        Self::Link,
+        Self::Md5Digest,
+        Self::Sha1Digest,
+        Self::Sha256Digest,
+        Self::Time,
+        Self::Device,
+        Self::ResDevice,
+    ];
+
+    /// The name of the column, which is the same as the mtree keyword.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Path => "path",
+            Self::Type => "type",
+            Self::Uid => "uid",
+            Self::Gid => "gid",
+            Self::Uname => "uname",
+            Self::Gname => "gname",
+            Self::Mode => "mode",
+            Self::Size => "size",
+            Self::Nlink => "nlink",
+            Self::Link => "link",
+            Self::Md5Digest => "md5digest",
+            Self::Sha1Digest => "sha1digest",
+            Self::Sha256Digest => "sha256digest",
+            Self::Time => "time",
+            Self::Device => "device",
+            Self::ResDevice => "resdevice",
+        }
+    }
+
+    /// The value of this column for the given entry. Missing values are empty.
+    fn value(&self, entry: &Entry) -> String {
+        fn opt<T: ToString>(value: Option<T>) -> String {
+            value.map(|v| v.to_string()).unwrap_or_default()
+        }
+
+        match self {
+            Self::Path => entry.path.to_string(),
+            Self::Type => opt(entry.path_type.map(|t| t.as_str())),
+            Self::Uid => opt(entry.uid),
+            Self::Gid => opt(entry.gid),
+            Self::Uname => opt(entry.uname.as_ref()),
+            Self::Gname => opt(entry.gname.as_ref()),
+            Self::Mode => opt(entry.mode),
+            Self::Size => opt(entry.size),
+            Self::Nlink => opt(entry.nlink),
+            Self::Link => opt(entry.link.as_ref()),
+            Self::Md5Digest => opt(entry.md5digest.as_ref()),
+            Self::Sha1Digest => opt(entry.sha1digest.as_ref()),
+            Self::Sha256Digest => opt(entry.sha256digest.as_ref()),
+            Self::Time => opt(entry.time),
+            Self::Device => opt(entry.device),
+            Self::ResDevice => opt(entry.resdevice),
+        }
+    }
+}
+
+impl FromStr for Column {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match Self::ALL.iter().find(|column| column.as_str() == s) {
+            Some(column) => Ok(*column),
+            None => bail!("unknown column: {s}"),
+        }
+    }
+}
+
+impl fmt::Display for Column {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Write the entries as a pretty-printed JSON array.
+pub fn write_json<W: Write>(writer: W, entries: &[Entry]) -> Result<()> {
+    serde_json::to_writer_pretty(writer, entries)?;
+    Ok(())
+}
+
+/// Write the entries as a YAML sequence.
+pub fn write_yaml<W: Write>(writer: W, entries: &[Entry]) -> Result<()> {
+    serde_yaml::to_writer(writer, entries)?;
+    Ok(())
+}
+
+/// Read entries from a JSON array as written by [write_json].
+pub fn read_json<'a>(input: &'a str) -> Result<Vec<Entry<'a>>> {
+    Ok(serde_json::from_str(input)?)
+}
+
+/// Read entries from a YAML sequence as written by [write_yaml].
+pub fn read_yaml<'a>(input: &'a str) -> Result<Vec<Entry<'a>>> {
+    Ok(serde_yaml::from_str(input)?)
+}
+
+/// Write the entries as CSV with a header row, only including the given columns.
+pub fn write_csv<W: Write>(writer: W, entries: &[Entry], columns: &[Column]) -> Result<()> {
+    let mut writer = csv::Writer::from_writer(writer);
+    writer.write_record(columns.iter().map(Column::as_str))?;
+    for entry in entries {
+        writer.write_record(columns.iter().map(|column| column.value(entry)))?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Write the files of a package like `pacman -Ql` lists them: one `<pkgname> <path>` line per
+/// entry, with absolute paths that end with a `/` for directories, sorted by path.
+///
+/// The metadata files at the root of a package, like `.PKGINFO` and `.MTREE`, aren't installed
+/// and are left out, as well as the root itself, see [Entry::is_metadata].
+pub fn write_file_list<W: Write>(mut writer: W, pkgname: &str, entries: &[Entry]) -> Result<()> {
+    let mut paths = entries
+        .iter()
+        .filter_map(|entry| {
+            let path = entry.path.strip_prefix("./").unwrap_or(&entry.path);
+            if matches!(path, "" | ".") || entry.is_metadata() {
+                return None;
+            }
+            let slash = if entry.path_type == Some(PathType::Dir) {
+                "/"
+            } else {
+                ""
+            };
+            Some(format!("/{}{slash}", path.trim_end_matches('/')))
+        })
+        .collect::<Vec<_>>();
+    paths.sort_unstable();
+    for path in paths {
+        writeln!(writer, "{pkgname} {path}")?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,143 @@
+use std::ops::{Bound, RangeBounds};
+
+use glob::{MatchOptions, Pattern, PatternError};
+
+use super::{DigestAlgorithm, Entry, Mode, PathType, Timestamp};
+
+/// A set of conditions that entries have to fulfill.
+///
+/// The conditions are chained, e.g. all setuid files that are larger than 1 MiB are found with
+/// `EntryFilter::new().path_type(PathType::File).mode(Mode::SETUID).size(1024 * 1024..)`.
+/// Entries that lack a property which is filtered for (e.g. no `size=`) never match.
+#[derive(Debug, Clone)]
+pub struct EntryFilter {
+    globs: Vec<Pattern>,
+    path_types: Vec<PathType>,
+    size: (Bound<usize>, Bound<usize>),
+    mode: Option<Mode>,
+    time: (Bound<Timestamp>, Bound<Timestamp>),
+    has_digest: Option<bool>,
+}
+
+impl Default for EntryFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EntryFilter {
+    /// A filter that matches every entry.
+    pub fn new() -> Self {
+        Self {
+            globs: Vec::new(),
+            path_types: Vec::new(),
+            size: (Bound::Unbounded, Bound::Unbounded),
+            mode: None,
+            time: (Bound::Unbounded, Bound::Unbounded),
+            has_digest: None,
+        }
+    }
+
+    /// Only match paths that match the glob pattern, e.g. `./usr/lib/*.so*`.
+    /// A `*` doesn't cross directory boundaries, use `**` for that.
+    /// Calling this multiple times matches any of the given patterns, e.g. to only verify a
+    /// few paths of a large manifest.
+    pub fn glob(mut self, pattern: &str) -> Result<Self, PatternError> {
+        self.globs.push(Pattern::new(pattern)?);
+        Ok(self)
+    }
+
+    /// Only match entries of the given type.
+    /// Calling this multiple times matches any of the given types.
+    pub fn path_type(mut self, path_type: PathType) -> Self {
+        self.path_types.push(path_type);
+        self
+    }
+
+    /// Only match entries whose size in bytes is in the given range.
+    pub fn size(mut self, range: impl RangeBounds<usize>) -> Self {
+        self.size = (range.start_bound().cloned(), range.end_bound().cloned());
+        self
+    }
+
+    /// Only match entries that have all of the given mode bits set.
+    pub fn mode(mut self, bits: Mode) -> Self {
+        self.mode = Some(bits);
+        self
+    }
+
+    /// Only match entries whose modification time is in the given range.
+    pub fn time(mut self, range: impl RangeBounds<Timestamp>) -> Self {
+        self.time = (range.start_bound().cloned(), range.end_bound().cloned());
+        self
+    }
+
+    /// Only match entries that do (or don't) have any kind of digest.
+    pub fn has_digest(mut self, has_digest: bool) -> Self {
+        self.has_digest = Some(has_digest);
+        self
+    }
+
+    /// Check whether a single entry fulfills all conditions of this filter.
+    pub fn matches(&self, entry: &Entry) -> bool {
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::new()
+        };
+
+        if !self.globs.is_empty()
+            && !self
+                .globs
+                .iter()
+                .any(|glob| glob.matches_with(&entry.path, options))
+        {
+            return false;
+        }
+
+        if !self.path_types.is_empty()
+            && !entry
+                .path_type
+                .is_some_and(|path_type| self.path_types.contains(&path_type))
+        {
+            return false;
+        }
+
+        if !in_range(&self.size, entry.size) || !in_range(&self.time, entry.time) {
+            return false;
+        }
+
+        if let Some(bits) = self.mode {
+            if !entry.mode.is_some_and(|mode| mode.contains(bits)) {
+                return false;
+            }
+        }
+
+        if let Some(has_digest) = self.has_digest {
+            let digest = DigestAlgorithm::ALL
+                .iter()
+                .any(|algorithm| algorithm.expected(entry).is_some());
+            if digest != has_digest {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Iterate over all given entries that match this filter.
+    pub fn apply<'e, 'a: 'e>(
+        &'e self,
+        entries: impl IntoIterator<Item = &'e Entry<'a>> + 'e,
+    ) -> impl Iterator<Item = &'e Entry<'a>> + 'e {
+        entries.into_iter().filter(|entry| self.matches(entry))
+    }
+}
+
+/// Whether the value is in the range. A missing value is only in an unbounded range.
+fn in_range<T: PartialOrd>(range: &(Bound<T>, Bound<T>), value: Option<T>) -> bool {
+    match (range, value) {
+        ((Bound::Unbounded, Bound::Unbounded), _) => true,
+        (_, None) => false,
+        (range, Some(value)) => range.contains(&value),
+    }
+}
@@ -0,0 +1,267 @@
+use super::{
+    backend::{Diag, MtreeBackend},
+    vis, Gid, Keyword, Mode, PathType, Property, Statement, Uid,
+};
+
+/// A hand-written parser that scans the input byte by byte.
+///
+/// It doesn't allocate anything but the returned statements and paths with escapes, which
+/// makes it the performance baseline for the parser libraries. It accepts exactly the same
+/// grammar as the chumsky parser in [super::parser].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HandRolledBackend;
+
+impl MtreeBackend for HandRolledBackend {
+    fn name(&self) -> &'static str {
+        "handrolled"
+    }
+
+    fn parse<'a>(&self, input: &'a str) -> Result<Vec<Statement<'a>>, Diag> {
+        Statements::new(input).collect()
+    }
+}
+
+/// The statements of a manifest, scanned one at a time.
+///
+/// After an error, scanning resumes at the next line.
+pub(crate) struct Statements<'a> {
+    scanner: Scanner<'a>,
+}
+
+impl<'a> Statements<'a> {
+    pub(crate) fn new(input: &'a str) -> Self {
+        Self {
+            scanner: Scanner { input, pos: 0 },
+        }
+    }
+}
+
+impl<'a> Iterator for Statements<'a> {
+    type Item = Result<Statement<'a>, Diag>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let scanner = &mut self.scanner;
+        let input = scanner.input;
+        if scanner.pos >= input.len() {
+            return None;
+        }
+
+        let start = scanner.pos;
+        let statement = scanner.statement().ok_or_else(|| {
+            scanner.pos = input[start..]
+                .find('\n')
+                .map_or(input.len(), |end| start + end + 1);
+            Diag::at(input, start)
+        });
+        scanner.skip_whitespace();
+
+        Some(statement)
+    }
+}
+
+/// A position in the input.
+///
+/// The parsing methods return `None` if the input doesn't match, they don't reset the
+/// position on their own.
+struct Scanner<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.pos).copied()
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        let matches = self.peek() == Some(byte);
+        if matches {
+            self.pos += 1;
+        }
+        matches
+    }
+
+    /// Consume all bytes that match the predicate, which must only match ASCII bytes or any
+    /// byte but a specific ASCII one, so the position stays on a char boundary.
+    fn take_while(&mut self, predicate: impl Fn(u8) -> bool) -> &'a str {
+        let start = self.pos;
+        let len = self.input.as_bytes()[start..]
+            .iter()
+            .take_while(|&&byte| predicate(byte))
+            .count();
+        self.pos += len;
+        &self.input[start..self.pos]
+    }
+
+    /// Like [Scanner::take_while], but at least one byte has to match.
+    fn take_while1(&mut self, predicate: impl Fn(u8) -> bool) -> Option<&'a str> {
+        Some(self.take_while(predicate)).filter(|s| !s.is_empty())
+    }
+
+    /// Skip any unicode whitespace, including newlines.
+    fn skip_whitespace(&mut self) {
+        let rest = &self.input[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// A line break, which may also be one of the unicode line separators.
+    fn newline(&mut self) -> bool {
+        if self.input[self.pos..].starts_with("\r\n") {
+            self.pos += 2;
+            return true;
+        }
+        match self.input[self.pos..].chars().next() {
+            Some(c @ ('\n' | '\r' | '\x0B' | '\x0C' | '\u{0085}' | '\u{2028}' | '\u{2029}')) => {
+                self.pos += c.len_utf8();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// An ASCII identifier, e.g. a keyword.
+    fn ident(&mut self) -> Option<&'a str> {
+        let start = self.pos;
+        if !self
+            .peek()
+            .is_some_and(|byte| byte.is_ascii_alphabetic() || byte == b'_')
+        {
+            return None;
+        }
+        self.pos += 1;
+        self.take_while(|byte| byte.is_ascii_alphanumeric() || byte == b'_');
+
+        Some(&self.input[start..self.pos])
+    }
+
+    fn digits(&mut self, radix: u32) -> Option<&'a str> {
+        self.take_while1(|byte| char::from(byte).is_digit(radix))
+    }
+
+    /// A decimal integer without leading zeros.
+    fn int(&mut self) -> Option<&'a str> {
+        match self.peek()? {
+            b'0' => {
+                self.pos += 1;
+                Some("0")
+            }
+            b'1'..=b'9' => self.digits(10),
+            _ => None,
+        }
+    }
+
+    /// A plain keyword value, which spans until the next whitespace.
+    fn value(&mut self) -> Option<&'a str> {
+        self.take_while1(|byte| !matches!(byte, b' ' | b'\t' | b'\n'))
+    }
+
+    fn timestamp(&mut self) -> Option<super::Timestamp> {
+        let start = self.pos;
+        self.eat(b'-');
+        self.digits(10)?;
+        let seconds = self.pos;
+        if !(self.eat(b'.') && self.digits(10).is_some()) {
+            self.pos = seconds;
+        }
+
+        self.input[start..self.pos].parse().ok()
+    }
+
+    fn path_type(&mut self) -> Option<PathType> {
+        Some(match self.ident()? {
+            "block" => PathType::Block,
+            "char" => PathType::Char,
+            "dir" => PathType::Dir,
+            "fifo" => PathType::Fifo,
+            "file" => PathType::File,
+            "link" => PathType::Link,
+            "socket" => PathType::Socket,
+            _ => return None,
+        })
+    }
+
+    /// A whitespace delimited list of items. The position is reset to the end of the last
+    /// item that matched.
+    fn repeated<T>(&mut self, item: impl Fn(&mut Self) -> Option<T>) -> Vec<T> {
+        let mut items = Vec::new();
+        loop {
+            let start = self.pos;
+            self.skip_whitespace();
+            match item(self) {
+                Some(value) => {
+                    items.push(value);
+                    self.skip_whitespace();
+                }
+                None => {
+                    self.pos = start;
+                    return items;
+                }
+            }
+        }
+    }
+
+    /// A single line of the manifest.
+    fn statement(&mut self) -> Option<Statement<'a>> {
+        match self.peek()? {
+            b'#' => {
+                self.pos += 1;
+                (self.ident()? == "mtree" && self.newline()).then_some(Statement::Init)
+            }
+            b'/' => {
+                self.pos += 1;
+                match self.ident()? {
+                    "set" => Some(Statement::Set(self.repeated(Self::property))),
+                    "unset" => Some(Statement::Unset(
+                        self.repeated(|scanner| Keyword::unset(scanner.ident()?))
+                            .concat(),
+                    )),
+                    _ => None,
+                }
+            }
+            b'.' => {
+                let path =
+                    vis::decode(self.take_while(|byte| !matches!(byte, b' ' | b'\t' | b'\n')));
+                let properties = self.repeated(Self::property);
+                Some(Statement::Path { path, properties })
+            }
+            _ => None,
+        }
+    }
+
+    /// A `keyword=` prefix.
+    fn keyword(&mut self) -> Option<&'a str> {
+        let keyword = self.ident()?;
+        self.eat(b'=').then_some(keyword)
+    }
+
+    fn property(&mut self) -> Option<Property<'a>> {
+        Some(match self.keyword()? {
+            "uid" => Property::Uid(Uid(self.digits(10)?.parse().ok()?)),
+            "gid" => Property::Gid(Gid(self.digits(10)?.parse().ok()?)),
+            "uname" => Property::Uname(vis::decode(self.value()?)),
+            "gname" => Property::Gname(vis::decode(self.value()?)),
+            "mode" => Property::Mode(Mode::from_octal(self.digits(8)?).ok()?),
+            "md5digest" | "md5" => Property::Md5Digest(self.digits(16)?),
+            "sha1digest" | "sha1" => Property::Sha1Digest(self.digits(16)?),
+            "sha256digest" | "sha256" => Property::Sha256Digest(self.digits(16)?),
+            "size" => Property::Size(self.digits(10)?.parse().ok()?),
+            "nlink" => Property::Nlink(self.int()?.parse().ok()?),
+            "time" => Property::Time(self.timestamp()?),
+            "type" => Property::Type(self.path_type()?),
+            "link" => Property::Link(vis::decode(self.value()?)),
+            "device" => Property::Device(self.value()?.parse().ok()?),
+            "resdevice" => Property::ResDevice(self.value()?.parse().ok()?),
+            _ => return None,
+        })
+    }
+}
+
+/// Parse a single `keyword=value` token of a path or a `/set` line, which has to match
+/// completely.
+pub(crate) fn property(token: &str) -> Option<Property<'_>> {
+    let mut scanner = Scanner {
+        input: token,
+        pos: 0,
+    };
+    scanner.property().filter(|_| scanner.pos == token.len())
+}
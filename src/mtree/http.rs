@@ -0,0 +1,14 @@
+use std::io::{self, Read};
+
+/// Start downloading a URL, e.g. a package on a mirror, and return the body as it arrives.
+///
+/// Nothing is written to disk, the body can be passed straight to [read](super::read) or
+/// [read_metadata](super::archive::read_metadata), which decompress it as needed.
+/// Responses with an error status are errors.
+#[tracing::instrument]
+pub fn get(url: &str) -> io::Result<impl Read + Send> {
+    let response = ureq::get(url).call().map_err(io::Error::other)?;
+    tracing::info!(status = response.status(), "downloading");
+
+    Ok(response.into_reader())
+}
@@ -0,0 +1,67 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// A numeric user id as given by the `uid=` keyword.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Uid(pub u32);
+
+/// A numeric group id as given by the `gid=` keyword.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Gid(pub u32);
+
+impl fmt::Display for Uid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl fmt::Display for Gid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Lookups in the system user database.
+///
+/// These are meant for verification tooling, which has to compare the `uname=`/`gname=` of a
+/// manifest with the numeric ids found on disk (or the other way around).
+/// All lookups return `Ok(None)` if there's no such user or group.
+#[cfg(all(unix, feature = "user-db"))]
+mod user_db {
+    use std::io;
+
+    use nix::unistd::{Group, User};
+
+    use super::{Gid, Uid};
+
+    impl Uid {
+        /// Look up the name of the user with this id.
+        pub fn user_name(&self) -> io::Result<Option<String>> {
+            let user = User::from_uid(nix::unistd::Uid::from_raw(self.0))?;
+            Ok(user.map(|user| user.name))
+        }
+
+        /// Look up the id of the user with the given name.
+        pub fn from_user_name(name: &str) -> io::Result<Option<Self>> {
+            let user = User::from_name(name)?;
+            Ok(user.map(|user| Self(user.uid.as_raw())))
+        }
+    }
+
+    impl Gid {
+        /// Look up the name of the group with this id.
+        pub fn group_name(&self) -> io::Result<Option<String>> {
+            let group = Group::from_gid(nix::unistd::Gid::from_raw(self.0))?;
+            Ok(group.map(|group| group.name))
+        }
+
+        /// Look up the id of the group with the given name.
+        pub fn from_group_name(name: &str) -> io::Result<Option<Self>> {
+            let group = Group::from_name(name)?;
+            Ok(group.map(|group| Self(group.gid.as_raw())))
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use std::{borrow::Cow, collections::BTreeMap, ops::Bound};
+
+use super::Entry;
+
+/// A lookup structure for resolved entries, sorted by path.
+///
+/// Lookups are `O(log n)`, which makes membership checks against huge manifests cheap.
+/// If a path shows up multiple times, the last entry wins.
+#[derive(Debug, Clone, Default)]
+pub struct MtreeIndex<'a> {
+    entries: BTreeMap<Cow<'a, str>, Entry<'a>>,
+}
+
+impl<'a> MtreeIndex<'a> {
+    pub fn new(entries: impl IntoIterator<Item = Entry<'a>>) -> Self {
+        entries.into_iter().collect()
+    }
+
+    /// Get the entry of the given path, e.g. `./usr/bin/zbarimg`.
+    pub fn get(&self, path: &str) -> Option<&Entry<'a>> {
+        self.entries.get(path)
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.entries.contains_key(path)
+    }
+
+    /// Iterate over all entries whose path starts with the given prefix, in path order.
+    ///
+    /// Note that this is a plain string prefix, so `./usr/lib` also matches `./usr/lib32`.
+    /// Use a trailing slash (`./usr/lib/`) to only get the contents of a directory.
+    pub fn iter_prefix<'s>(&'s self, prefix: &'s str) -> impl Iterator<Item = &'s Entry<'a>> + 's {
+        self.entries
+            .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+            .take_while(move |(path, _)| path.starts_with(prefix))
+            .map(|(_, entry)| entry)
+    }
+
+    /// Iterate over all entries in path order.
+    pub fn iter(&self) -> impl Iterator<Item = &Entry<'a>> {
+        self.entries.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl<'a> FromIterator<Entry<'a>> for MtreeIndex<'a> {
+    fn from_iter<T: IntoIterator<Item = Entry<'a>>>(iter: T) -> Self {
+        Self {
+            entries: iter
+                .into_iter()
+                .map(|entry| (entry.path.clone(), entry))
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use std::{
+    fmt,
+    io::{self, Read},
+};
+
+use super::{Diagnostic, Statement};
+
+/// Caps on the size of a manifest, which guard against malicious input like decompression
+/// bombs, since manifests often come from untrusted downloads.
+///
+/// The defaults are far beyond what real packages need.
+///
+/// The handrolled parser and the line by line recovery of [ParseOptions](super::ParseOptions)
+/// check the statements while they parse them and stop at the first one over the limits. The
+/// other parsers build all statements before they are checked, so only the line length and the
+/// size of the manifest bound the memory they use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// The number of statements in a manifest, including `/set` and `/unset`.
+    pub max_statements: usize,
+    /// The number of properties of a single statement.
+    pub max_properties: usize,
+    /// The length of a single line in bytes.
+    pub max_line_length: usize,
+    /// The size of the whole manifest in bytes, after decompression.
+    pub max_decompressed_size: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_statements: 1_000_000,
+            max_properties: 64,
+            max_line_length: 64 * 1024,
+            max_decompressed_size: 256 * 1024 * 1024,
+        }
+    }
+}
+
+impl Limits {
+    /// No limits at all, for trusted input.
+    pub const UNLIMITED: Self = Self {
+        max_statements: usize::MAX,
+        max_properties: usize::MAX,
+        max_line_length: usize::MAX,
+        max_decompressed_size: usize::MAX,
+    };
+
+    /// Check the size and the line lengths of a manifest before it's parsed.
+    pub fn check_input(&self, input: &str) -> Result<(), LimitExceeded> {
+        if input.len() > self.max_decompressed_size {
+            return Err(LimitExceeded::new(
+                Limit::DecompressedSize,
+                self.max_decompressed_size,
+            ));
+        }
+        if input.lines().any(|line| line.len() > self.max_line_length) {
+            return Err(LimitExceeded::new(Limit::LineLength, self.max_line_length));
+        }
+
+        Ok(())
+    }
+
+    /// Check the number of statements and properties of a parsed manifest.
+    pub fn check_statements(&self, statements: &[Statement]) -> Result<(), LimitExceeded> {
+        if statements.len() > self.max_statements {
+            return Err(LimitExceeded::new(Limit::Statements, self.max_statements));
+        }
+        statements
+            .iter()
+            .try_for_each(|statement| self.check_statement(0, statement))
+    }
+
+    /// Check a statement while the manifest is parsed, `count` is the number of statements so
+    /// far, including this one.
+    pub fn check_statement(
+        &self,
+        count: usize,
+        statement: &Statement,
+    ) -> Result<(), LimitExceeded> {
+        if count > self.max_statements {
+            return Err(LimitExceeded::new(Limit::Statements, self.max_statements));
+        }
+        let properties = match statement {
+            Statement::Init => 0,
+            Statement::Set(properties) => properties.len(),
+            Statement::Unset(keywords) => keywords.len(),
+            Statement::Path { properties, .. } => properties.len(),
+        };
+        if properties > self.max_properties {
+            return Err(LimitExceeded::new(Limit::Properties, self.max_properties));
+        }
+
+        Ok(())
+    }
+
+    /// Collect the statements of a parser that yields them one at a time, but stop at the first
+    /// one over the limits instead of parsing the rest of the manifest. The inner result is the
+    /// first error of the parser.
+    pub(crate) fn collect<'a, E>(
+        &self,
+        statements: impl Iterator<Item = Result<Statement<'a>, E>>,
+    ) -> Result<Result<Vec<Statement<'a>>, E>, LimitExceeded> {
+        let mut collected = Vec::new();
+        for statement in statements {
+            let statement = match statement {
+                Ok(statement) => statement,
+                Err(err) => return Ok(Err(err)),
+            };
+            self.check_statement(collected.len() + 1, &statement)?;
+            collected.push(statement);
+        }
+
+        Ok(Ok(collected))
+    }
+
+    /// Read a whole (decompressed) manifest, but at most `max_decompressed_size` bytes of it.
+    ///
+    /// Exceeding the limit is an [io::ErrorKind::InvalidData] error that wraps the
+    /// [LimitExceeded], reading stops right after the limit. A byte order mark is dropped and
+    /// UTF-16 is transcoded, see [decode](super::encoding::decode).
+    pub fn read_to_string<R: Read>(&self, reader: R) -> io::Result<String> {
+        super::encoding::decode(self.read_to_end(reader)?)
+    }
+
+    /// Like [read_to_string](Self::read_to_string), but return the bytes as they are.
+    pub fn read_to_end<R: Read>(&self, reader: R) -> io::Result<Vec<u8>> {
+        let max = self.max_decompressed_size;
+        let mut content = Vec::new();
+        reader
+            .take(u64::try_from(max).unwrap_or(u64::MAX).saturating_add(1))
+            .read_to_end(&mut content)?;
+        if content.len() > max {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                LimitExceeded::new(Limit::DecompressedSize, max),
+            ));
+        }
+
+        Ok(content)
+    }
+}
+
+/// Which of the [Limits] was exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Statements,
+    Properties,
+    LineLength,
+    DecompressedSize,
+}
+
+/// The error when a manifest exceeds one of the [Limits].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LimitExceeded {
+    pub limit: Limit,
+    /// The configured maximum.
+    pub max: usize,
+}
+
+impl LimitExceeded {
+    fn new(limit: Limit, max: usize) -> Self {
+        Self { limit, max }
+    }
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let max = self.max;
+        match self.limit {
+            Limit::Statements => write!(f, "manifest has more than {max} statements"),
+            Limit::Properties => write!(f, "a statement has more than {max} properties"),
+            Limit::LineLength => write!(f, "a line is longer than {max} bytes"),
+            Limit::DecompressedSize => write!(f, "manifest is larger than {max} bytes"),
+        }
+    }
+}
+
+impl std::error::Error for LimitExceeded {}
+
+impl From<LimitExceeded> for Diagnostic {
+    fn from(err: LimitExceeded) -> Self {
+        Self::error("limit-exceeded", err.to_string())
+    }
+}
@@ -0,0 +1,225 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use rayon::prelude::*;
+use serde::Serialize;
+
+#[cfg(not(feature = "digests"))]
+use super::verify::verify_entry;
+use super::{
+    parse_file, Backend, DuplicatePolicy, Entry, Limits, Mismatch, MismatchKind, ParseOptions,
+    Progress,
+};
+#[cfg(feature = "digests")]
+use super::{verify::verify_entry_with_digests, DigestAlgorithm};
+
+/// An installed package of the local pacman database, a `<dbpath>/local/<name>-<version>`
+/// directory with the `desc` and the `mtree` of the package.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Package {
+    pub name: String,
+    pub version: String,
+    /// The configuration files that pacman keeps when they were changed, relative to the root
+    /// and without a leading `./`, e.g. `etc/pacman.conf`.
+    #[serde(skip)]
+    pub backup: Vec<String>,
+    /// The directory of the package in the database.
+    #[serde(skip)]
+    pub dir: PathBuf,
+}
+
+impl Package {
+    /// Read the name, the version and the backup files of a package from the `desc` file in its
+    /// directory.
+    pub fn read(dir: &Path) -> io::Result<Self> {
+        let path = dir.join("desc");
+        let desc = fs::read_to_string(&path)
+            .map_err(|err| io::Error::new(err.kind(), format!("{}: {err}", path.display())))?;
+        let field = |name: &str| {
+            desc_field(&desc, name).map(String::from).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}/desc has no %{name}%", dir.display()),
+                )
+            })
+        };
+
+        Ok(Self {
+            name: field("NAME")?,
+            version: field("VERSION")?,
+            // Each line is the path and the MD5 digest of the file when it was installed.
+            backup: desc_values(&desc, "BACKUP")
+                .filter_map(|line| line.split('\t').next())
+                .map(String::from)
+                .collect(),
+            dir: dir.to_path_buf(),
+        })
+    }
+
+    /// The manifest of the installed files.
+    pub fn mtree(&self) -> PathBuf {
+        self.dir.join("mtree")
+    }
+}
+
+/// The first value of a `%NAME%` section of a `desc` file.
+fn desc_field<'a>(desc: &'a str, name: &str) -> Option<&'a str> {
+    desc_values(desc, name).next()
+}
+
+/// The values of a `%NAME%` section of a `desc` file, one per line up to the next blank line.
+fn desc_values<'a>(desc: &'a str, name: &str) -> impl Iterator<Item = &'a str> {
+    let mut lines = desc.lines();
+    let found = lines
+        .find(|line| {
+            line.strip_prefix('%')
+                .and_then(|line| line.strip_suffix('%'))
+                == Some(name)
+        })
+        .is_some();
+    lines.take_while(move |value| found && !value.is_empty())
+}
+
+/// Read all installed packages of the pacman database at `dbpath`, e.g. `/var/lib/pacman`,
+/// sorted by their directories. Entries of `local` that aren't package directories are
+/// skipped.
+///
+/// There is one result per package, a package whose `desc` can't be read doesn't stop the
+/// others. Only a database that can't be listed is an error.
+pub fn packages(dbpath: &Path) -> io::Result<Vec<io::Result<Package>>> {
+    let mut dirs = Vec::new();
+    for entry in fs::read_dir(dbpath.join("local"))? {
+        let dir = entry?.path();
+        if dir.join("desc").is_file() {
+            dirs.push(dir);
+        }
+    }
+    dirs.sort_unstable();
+
+    Ok(dirs.iter().map(|dir| Package::read(dir)).collect())
+}
+
+/// Options for [check].
+#[derive(Debug, Clone, Default)]
+pub struct CheckOptions {
+    /// The number of threads to check packages with, `0` uses one thread per CPU.
+    pub threads: usize,
+    pub backend: Backend,
+    pub limits: Limits,
+    /// Receives every checked package.
+    pub progress: Option<Arc<dyn Progress>>,
+}
+
+/// The result of checking the files of one package.
+#[derive(Debug, Clone, Serialize)]
+pub struct PackageCheck {
+    #[serde(flatten)]
+    pub package: Package,
+    /// The number of entries that were checked.
+    pub files: usize,
+    pub mismatches: Vec<Mismatch>,
+    /// Why the manifest of the package couldn't be read or parsed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl PackageCheck {
+    /// The number of paths with at least one mismatch, like the altered files of `pacman -Qkk`.
+    pub fn altered(&self) -> usize {
+        let mut paths = self
+            .mismatches
+            .iter()
+            .map(|mismatch| &mismatch.path)
+            .collect::<Vec<_>>();
+        paths.dedup();
+        paths.len()
+    }
+}
+
+/// Check the installed files of every package against its manifest, like `pacman -Qkk`.
+///
+/// The paths are relative to `root`. The metadata files of a package, like `.PKGINFO`, aren't
+/// installed and are skipped. With the `digests` feature, the digests of the files are checked
+/// too. Like pacman, the contents and modification times of backup files aren't checked.
+///
+/// Packages are checked in parallel on a dedicated thread pool, there is one result per
+/// package, in the same order.
+#[tracing::instrument(skip(packages, options), fields(packages = packages.len(), root = %root.display()))]
+pub fn check(
+    packages: &[Package],
+    root: &Path,
+    options: &CheckOptions,
+) -> Result<Vec<PackageCheck>, rayon::ThreadPoolBuildError> {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(options.threads)
+        .build()?;
+    let progress = options.progress.as_deref().unwrap_or(&());
+
+    progress.start(packages.len() as u64, 0);
+    let checks = pool.install(|| {
+        packages
+            .par_iter()
+            .map(|package| {
+                let check = check_package(package, root, options);
+                progress.advance(0);
+                check
+            })
+            .collect()
+    });
+    progress.finish();
+
+    Ok(checks)
+}
+
+/// Check the installed files of a single package.
+fn check_package(package: &Package, root: &Path, options: &CheckOptions) -> PackageCheck {
+    let mut check = PackageCheck {
+        package: package.clone(),
+        files: 0,
+        mismatches: Vec::new(),
+        error: None,
+    };
+    let parse = ParseOptions {
+        backend: options.backend,
+        limits: options.limits,
+        ..ParseOptions::default()
+    };
+    match parse_file(&package.mtree(), parse, DuplicatePolicy::default()) {
+        Ok(parsed) => {
+            let installed = parsed.entries.iter().filter(|entry| !entry.is_metadata());
+            check.files = installed.clone().count();
+            check.mismatches = installed
+                .flat_map(|entry| check_entry(package, entry, root))
+                .collect();
+        }
+        Err(err) => check.error = Some(err.to_string()),
+    }
+
+    check
+}
+
+/// Check a single installed file, with its digests if the `digests` feature is enabled.
+///
+/// Like pacman, the contents and the modification time of backup files aren't checked, since
+/// they are expected to be changed.
+fn check_entry(package: &Package, entry: &Entry, root: &Path) -> Vec<Mismatch> {
+    #[cfg(feature = "digests")]
+    let mut mismatches = verify_entry_with_digests(entry, root, DigestAlgorithm::ALL);
+    #[cfg(not(feature = "digests"))]
+    let mut mismatches = verify_entry(entry, root);
+
+    let path = entry.path.strip_prefix("./").unwrap_or(&entry.path);
+    if package.backup.iter().any(|backup| backup == path) {
+        mismatches.retain(|mismatch| {
+            !matches!(
+                mismatch.kind,
+                MismatchKind::Size { .. } | MismatchKind::Digest { .. } | MismatchKind::Time { .. }
+            )
+        });
+    }
+
+    mismatches
+}
@@ -0,0 +1,80 @@
+use std::{
+    borrow::Cow,
+    collections::{btree_map, BTreeMap},
+    fmt,
+};
+
+use super::{Entries, Entry};
+
+/// What to do if the same path shows up in multiple manifests with different properties.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictPolicy {
+    /// Abort the merge with a [MergeConflict].
+    #[default]
+    Error,
+    /// Keep the entry of the manifest that was given first.
+    FirstWins,
+    /// Keep the entry with the most recent `time=`.
+    /// Entries without a time count as the oldest, on a tie the first one wins.
+    NewestMtimeWins,
+}
+
+/// The error of a merge with [ConflictPolicy::Error].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeConflict {
+    /// The path that differs between the manifests.
+    pub path: String,
+}
+
+impl fmt::Display for MergeConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "conflicting entries for {}", self.path)
+    }
+}
+
+impl std::error::Error for MergeConflict {}
+
+/// Combine the entries of several manifests into a single set, sorted by path in
+/// [canonical order](super::compare_paths).
+///
+/// Identical entries for the same path are never a conflict, otherwise the given policy
+/// decides which entry is kept.
+pub fn merge<'a, I>(manifests: I, policy: ConflictPolicy) -> Result<Vec<Entry<'a>>, MergeConflict>
+where
+    I: IntoIterator,
+    I::Item: IntoIterator<Item = Entry<'a>>,
+{
+    let mut merged: BTreeMap<Cow<'a, str>, Entry<'a>> = BTreeMap::new();
+
+    for entry in manifests.into_iter().flatten() {
+        let mut existing = match merged.entry(entry.path.clone()) {
+            btree_map::Entry::Vacant(vacant) => {
+                vacant.insert(entry);
+                continue;
+            }
+            btree_map::Entry::Occupied(existing) => existing,
+        };
+        if *existing.get() == entry {
+            continue;
+        }
+
+        match policy {
+            ConflictPolicy::Error => {
+                return Err(MergeConflict {
+                    path: entry.path.into_owned(),
+                })
+            }
+            ConflictPolicy::FirstWins => {}
+            ConflictPolicy::NewestMtimeWins => {
+                if entry.time > existing.get().time {
+                    existing.insert(entry);
+                }
+            }
+        }
+    }
+
+    let mut merged = merged.into_values().collect::<Vec<_>>();
+    merged.sort_canonical();
+
+    Ok(merged)
+}
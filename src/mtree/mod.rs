@@ -0,0 +1,388 @@
+//! Types and parsers for the mtree(5) file format as used by the `.MTREE` file of ALPM packages.
+
+use std::{borrow::Cow, fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "alpm-types")]
+pub mod alpm;
+#[cfg(feature = "tar")]
+pub mod archive;
+pub mod audit;
+pub mod backend;
+pub mod bulk;
+pub mod create;
+pub mod device;
+pub mod diagnostic;
+pub mod diff;
+pub mod digest;
+pub mod duplicates;
+pub mod encoding;
+pub mod entry;
+pub mod exclude;
+pub mod export;
+pub mod filter;
+pub mod handrolled;
+#[cfg(feature = "http")]
+pub mod http;
+pub mod id;
+pub mod index;
+pub mod limits;
+pub mod localdb;
+pub mod merge;
+pub mod mode;
+pub mod nom_parser;
+pub mod normalize;
+pub mod options;
+pub mod parser;
+pub mod pkginfo;
+pub mod progress;
+#[cfg(feature = "digests")]
+pub mod refresh;
+pub mod sarif;
+pub mod sbom;
+#[cfg(feature = "signatures")]
+pub mod signature;
+pub mod sniff;
+pub mod stats;
+pub mod time;
+pub mod verify;
+pub mod vis;
+pub mod visitor;
+pub mod writer;
+
+#[cfg(feature = "alpm-types")]
+pub use alpm::PackageInfo;
+pub use audit::{audit, Finding, FindingKind};
+pub use backend::{Backend, Diag, MtreeBackend};
+pub use bulk::{
+    parse_file, parse_many, parse_many_with_progress, read, read_file, read_strict, ParseFileError,
+    ParsedFile,
+};
+pub use create::{create, source_date_epoch, CreateOptions};
+pub use device::{Device, DeviceFormat};
+pub use diagnostic::{Diagnostic, Severity};
+pub use diff::{diff, Change, ChangeKind, PropertyChange};
+pub use digest::DigestAlgorithm;
+pub use duplicates::{duplicates, Duplicate};
+pub use encoding::Encoding;
+pub use entry::{
+    compare_paths, find_entry, find_entry_with, resolve, resolve_with, DuplicatePolicy, Entries,
+    Entry, Resolved, METADATA_FILES,
+};
+pub use exclude::Excludes;
+pub use filter::EntryFilter;
+pub use id::{Gid, Uid};
+pub use index::MtreeIndex;
+pub use limits::{Limit, LimitExceeded, Limits};
+pub use merge::{merge, ConflictPolicy, MergeConflict};
+pub use mode::Mode;
+pub use normalize::{normalize, NormalizeOptions};
+pub use options::{ParseOptions, Parsed, Strictness};
+pub use parser::parser;
+pub use pkginfo::{parse_info, Info, InfoOptions, Separator};
+pub use progress::Progress;
+#[cfg(feature = "digests")]
+pub use refresh::{refresh, RefreshOptions};
+pub use sniff::{sniff, sniff_file, Compression, FileFormat};
+pub use stats::Stats;
+pub use time::Timestamp;
+pub use verify::{verify, Mismatch, MismatchKind};
+pub use visitor::{visit, MtreeVisitor};
+
+/// Each line represents a line in a .MTREE file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Statement<'a> {
+    /// The initial `#mtree` line at the top of the file
+    Init,
+    /// A `/set` command followed by some properties, which can be any property of a path
+    #[serde(borrow)]
+    Set(Vec<Property<'a>>),
+    /// A `/unset` command followed by the keywords of the defaults to remove, `all` stands
+    /// for every keyword
+    Unset(Vec<Keyword>),
+    /// Any path statement followed by some properties
+    Path {
+        #[serde(borrow)]
+        path: Cow<'a, str>,
+        properties: Vec<Property<'a>>,
+    },
+}
+
+/// This type is used in a [Path] line and defines some available properties for that path.
+/// The same properties can be given to `/set` and `/unset` to change the defaults.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Property<'a> {
+    Uid(Uid),
+    Gid(Gid),
+    #[serde(borrow)]
+    Uname(Cow<'a, str>),
+    #[serde(borrow)]
+    Gname(Cow<'a, str>),
+    Mode(Mode),
+    Type(PathType),
+    Size(usize),
+    Nlink(usize),
+    #[serde(borrow)]
+    Link(Cow<'a, str>),
+    Md5Digest(&'a str),
+    Sha1Digest(&'a str),
+    Sha256Digest(&'a str),
+    Time(Timestamp),
+    Device(Device),
+    ResDevice(Device),
+}
+
+// What kind of type is a path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PathType {
+    Block,
+    Char,
+    Dir,
+    Fifo,
+    File,
+    Link,
+    Socket,
+}
+
+impl PathType {
+    /// The name of the type as used in a manifest.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Block => "block",
+            Self::Char => "char",
+            Self::Dir => "dir",
+            Self::Fifo => "fifo",
+            Self::File => "file",
+            Self::Link => "link",
+            Self::Socket => "socket",
+        }
+    }
+}
+
+impl fmt::Display for PathType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for PathType {
+    type Err = ParsePathTypeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "block" => Ok(Self::Block),
+            "char" => Ok(Self::Char),
+            "dir" => Ok(Self::Dir),
+            "fifo" => Ok(Self::Fifo),
+            "file" => Ok(Self::File),
+            "link" => Ok(Self::Link),
+            "socket" => Ok(Self::Socket),
+            _ => Err(ParsePathTypeError),
+        }
+    }
+}
+
+/// The error when parsing an unknown [PathType].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParsePathTypeError;
+
+impl fmt::Display for ParsePathTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown path type")
+    }
+}
+
+impl std::error::Error for ParsePathTypeError {}
+
+impl Property<'_> {
+    /// The keyword of the property, e.g. `uid`. Digests use their long form, e.g. `md5digest`.
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            Self::Uid(_) => "uid",
+            Self::Gid(_) => "gid",
+            Self::Uname(_) => "uname",
+            Self::Gname(_) => "gname",
+            Self::Mode(_) => "mode",
+            Self::Type(_) => "type",
+            Self::Size(_) => "size",
+            Self::Nlink(_) => "nlink",
+            Self::Link(_) => "link",
+            Self::Md5Digest(_) => "md5digest",
+            Self::Sha1Digest(_) => "sha1digest",
+            Self::Sha256Digest(_) => "sha256digest",
+            Self::Time(_) => "time",
+            Self::Device(_) => "device",
+            Self::ResDevice(_) => "resdevice",
+        }
+    }
+}
+
+/// The keyword of a property without its value, as given to `/unset`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Keyword {
+    Uid,
+    Gid,
+    Uname,
+    Gname,
+    Mode,
+    Type,
+    Size,
+    Nlink,
+    Link,
+    Md5Digest,
+    Sha1Digest,
+    Sha256Digest,
+    Time,
+    Device,
+    ResDevice,
+}
+
+impl Keyword {
+    pub const ALL: &'static [Keyword] = &[
+        Self::Uid,
+        Self::Gid,
+        Self::Uname,
+        Self::Gname,
+        Self::Mode,
+        Self::Type,
+        Self::Size,
+        Self::Nlink,
+        Self::Link,
+        Self::Md5Digest,
+        Self::Sha1Digest,
+        Self::Sha256Digest,
+        Self::Time,
+        Self::Device,
+        Self::ResDevice,
+    ];
+
+    /// The keyword as written in a manifest. Digests use their long form, e.g. `md5digest`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Uid => "uid",
+            Self::Gid => "gid",
+            Self::Uname => "uname",
+            Self::Gname => "gname",
+            Self::Mode => "mode",
+            Self::Type => "type",
+            Self::Size => "size",
+            Self::Nlink => "nlink",
+            Self::Link => "link",
+            Self::Md5Digest => "md5digest",
+            Self::Sha1Digest => "sha1digest",
+            Self::Sha256Digest => "sha256digest",
+            Self::Time => "time",
+            Self::Device => "device",
+            Self::ResDevice => "resdevice",
+        }
+    }
+
+    /// The keywords that a word of a `/unset` line stands for, `all` is every keyword.
+    pub(crate) fn unset(word: &str) -> Option<Vec<Keyword>> {
+        match word {
+            "all" => Some(Self::ALL.to_vec()),
+            word => word.parse().ok().map(|keyword| vec![keyword]),
+        }
+    }
+}
+
+impl fmt::Display for Keyword {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Keyword {
+    type Err = anyhow::Error;
+
+    /// Parse a keyword, including the short digest aliases like `md5`.
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let s = match s {
+            "md5" | "sha1" | "sha256" => &format!("{s}digest"),
+            s => s,
+        };
+        match Self::ALL.iter().find(|keyword| keyword.as_str() == s) {
+            Some(keyword) => Ok(*keyword),
+            None => anyhow::bail!("unknown keyword: {s}"),
+        }
+    }
+}
+
+/// Formats the property as `keyword=value`, with the names and link target vis-encoded.
+impl fmt::Display for Property<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}=", self.keyword())?;
+        match self {
+            Self::Uid(uid) => write!(f, "{uid}"),
+            Self::Gid(gid) => write!(f, "{gid}"),
+            Self::Uname(s) | Self::Gname(s) | Self::Link(s) => write!(f, "{}", vis::encode(s)),
+            Self::Mode(mode) => write!(f, "{mode}"),
+            Self::Type(path_type) => write!(f, "{path_type}"),
+            Self::Size(n) | Self::Nlink(n) => write!(f, "{n}"),
+            Self::Md5Digest(digest) | Self::Sha1Digest(digest) | Self::Sha256Digest(digest) => {
+                write!(f, "{digest}")
+            }
+            Self::Time(time) => write!(f, "{time}"),
+            Self::Device(device) | Self::ResDevice(device) => write!(f, "{device}"),
+        }
+    }
+}
+
+/// Parses a single `keyword=value` property of a path line.
+impl<'a> TryFrom<&'a str> for Property<'a> {
+    type Error = Diag;
+
+    fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        handrolled::property(s).ok_or_else(|| Diag::at(s, 0))
+    }
+}
+
+/// Formats the statement as a single manifest line, without the line break.
+impl fmt::Display for Statement<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Init => write!(f, "#mtree"),
+            Self::Set(properties) => {
+                write!(f, "/set")?;
+                properties.iter().try_for_each(|p| write!(f, " {p}"))
+            }
+            Self::Unset(keywords) => {
+                write!(f, "/unset")?;
+                keywords.iter().try_for_each(|k| write!(f, " {k}"))
+            }
+            Self::Path { path, properties } => {
+                write!(f, "{}", vis::encode(path))?;
+                properties.iter().try_for_each(|p| write!(f, " {p}"))
+            }
+        }
+    }
+}
+
+/// Parses a single manifest line, the line break at its end is optional.
+///
+/// `FromStr` isn't implemented since the statement borrows from the line.
+impl<'a> TryFrom<&'a str> for Statement<'a> {
+    type Error = Diag;
+
+    fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        if s.trim_end() == "#mtree" {
+            return Ok(Self::Init);
+        }
+
+        let mut statements = handrolled::Statements::new(s);
+        match (statements.next(), statements.next()) {
+            (Some(statement), None) => statement,
+            (Some(Err(err)), _) => Err(err),
+            (Some(Ok(_)), Some(_)) => Err(Diag {
+                span: 0..s.len(),
+                message: String::from("expected a single statement"),
+            }),
+            (None, _) => Err(Diag::at(s, 0)),
+        }
+    }
+}
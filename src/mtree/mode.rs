@@ -0,0 +1,118 @@
+use std::{fmt, str::FromStr};
+
+use bitflags::bitflags;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+bitflags! {
+    /// The permission bits of a path as given by a `mode=` keyword.
+    ///
+    /// Next to the usual `rwx` triplets this also covers the setuid, setgid and sticky bits,
+    /// which are set via the 4-digit octal form (e.g. `4755`).
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct Mode: u32 {
+        const SETUID = 0o4000;
+        const SETGID = 0o2000;
+        const STICKY = 0o1000;
+
+        const OWNER_READ = 0o400;
+        const OWNER_WRITE = 0o200;
+        const OWNER_EXEC = 0o100;
+
+        const GROUP_READ = 0o040;
+        const GROUP_WRITE = 0o020;
+        const GROUP_EXEC = 0o010;
+
+        const OTHER_READ = 0o004;
+        const OTHER_WRITE = 0o002;
+        const OTHER_EXEC = 0o001;
+    }
+}
+
+impl Mode {
+    /// Parse a mode from its octal representation, e.g. `644` or `4755`.
+    pub fn from_octal(s: &str) -> Result<Self, ParseModeError> {
+        if s.is_empty() {
+            return Err(ParseModeError::Empty);
+        }
+        // Anything beyond 4 octal digits can't be represented by the permission bits.
+        if s.len() > 4 {
+            return Err(ParseModeError::OutOfRange);
+        }
+        let bits = u32::from_str_radix(s, 8).map_err(|_| ParseModeError::InvalidDigit)?;
+
+        Self::from_bits(bits).ok_or(ParseModeError::OutOfRange)
+    }
+
+    /// Whether anybody may write to this path.
+    pub fn is_world_writable(&self) -> bool {
+        self.contains(Self::OTHER_WRITE)
+    }
+
+    pub fn is_setuid(&self) -> bool {
+        self.contains(Self::SETUID)
+    }
+
+    pub fn is_setgid(&self) -> bool {
+        self.contains(Self::SETGID)
+    }
+
+    pub fn is_sticky(&self) -> bool {
+        self.contains(Self::STICKY)
+    }
+}
+
+impl FromStr for Mode {
+    type Err = ParseModeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_octal(s)
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:o}", self.bits())
+    }
+}
+
+/// Modes are serialized as their octal string, e.g. `"4755"`.
+impl Serialize for Mode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Mode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Self::from_octal(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(unix)]
+impl From<Mode> for std::fs::Permissions {
+    fn from(mode: Mode) -> Self {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::Permissions::from_mode(mode.bits())
+    }
+}
+
+/// Errors that can occur when parsing a [Mode].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseModeError {
+    Empty,
+    InvalidDigit,
+    OutOfRange,
+}
+
+impl fmt::Display for ParseModeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "mode is empty"),
+            Self::InvalidDigit => write!(f, "mode contains a non-octal digit"),
+            Self::OutOfRange => write!(f, "mode must be in the range 0..=7777"),
+        }
+    }
+}
+
+impl std::error::Error for ParseModeError {}
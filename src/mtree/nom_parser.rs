@@ -0,0 +1,230 @@
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, take_while, take_while1},
+    character::complete::{char, satisfy},
+    combinator::{map, map_opt, map_res, opt, recognize, value, verify},
+    multi::many0,
+    sequence::{delimited, preceded},
+    IResult, Parser,
+};
+
+use super::{
+    backend::{Diag, MtreeBackend},
+    vis, Device, Gid, Keyword, Mode, PathType, Property, Statement, Timestamp, Uid,
+};
+
+/// The parser built with [nom](https://docs.rs/nom).
+///
+/// It accepts exactly the same grammar as the chumsky parser in [super::parser].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NomBackend;
+
+impl MtreeBackend for NomBackend {
+    fn name(&self) -> &'static str {
+        "nom"
+    }
+
+    fn parse<'a>(&self, input: &'a str) -> Result<Vec<Statement<'a>>, Diag> {
+        let mut statements = Vec::new();
+        let mut rest = input;
+        while !rest.is_empty() {
+            match statement(rest) {
+                Ok((remaining, statement)) => {
+                    statements.push(statement);
+                    rest = remaining.trim_start();
+                }
+                Err(nom::Err::Error(err) | nom::Err::Failure(err)) => {
+                    return Err(Diag::at(input, input.len() - err.input.len()))
+                }
+                Err(nom::Err::Incomplete(_)) => return Err(Diag::at(input, input.len())),
+            }
+        }
+
+        Ok(statements)
+    }
+}
+
+/// A single line of the manifest.
+fn statement(input: &str) -> IResult<&str, Statement<'_>> {
+    // The very first line of the `.MTREE` file.
+    let init = value(Statement::Init, (char('#'), keyword("mtree"), newline));
+    // `/set`, followed by a whitespace delimited list of properties, and `/unset`, followed by
+    // one of keywords without values or `all`.
+    let set = map(
+        preceded((char('/'), keyword("set")), many0(padded(property))),
+        Statement::Set,
+    );
+    let unset = map(
+        preceded((char('/'), keyword("unset")), many0(padded(unset_keyword))),
+        |keywords| Statement::Unset(keywords.concat()),
+    );
+    // A path line, which starts with a `.` and is delimited by a whitespace.
+    let path = map(
+        (
+            map(
+                recognize((char('.'), take_while(|c| !matches!(c, ' ' | '\t' | '\n')))),
+                vis::decode,
+            ),
+            many0(padded(property)),
+        ),
+        |(path, properties)| Statement::Path { path, properties },
+    );
+
+    alt((init, set, unset, path)).parse(input)
+}
+
+/// The properties of a path line or a `/set` command.
+fn property(input: &str) -> IResult<&str, Property<'_>> {
+    alt((
+        map(assign("uid", id), |id| Property::Uid(Uid(id))),
+        map(assign("gid", id), |id| Property::Gid(Gid(id))),
+        map(
+            assign("uname", map(value_str, vis::decode)),
+            Property::Uname,
+        ),
+        map(
+            assign("gname", map(value_str, vis::decode)),
+            Property::Gname,
+        ),
+        map(assign("mode", mode), Property::Mode),
+        map(digest("md5digest", "md5"), Property::Md5Digest),
+        map(digest("sha1digest", "sha1"), Property::Sha1Digest),
+        map(digest("sha256digest", "sha256"), Property::Sha256Digest),
+        map(
+            assign("size", map_res(digits(10), str::parse)),
+            Property::Size,
+        ),
+        map(assign("nlink", map_res(int, str::parse)), Property::Nlink),
+        map(assign("time", timestamp), Property::Time),
+        map(assign("type", path_type), Property::Type),
+        map(assign("link", map(value_str, vis::decode)), Property::Link),
+        map(assign("device", device), Property::Device),
+        map(assign("resdevice", device), Property::ResDevice),
+    ))
+    .parse(input)
+}
+
+/// A keyword of a `/unset` command, `all` is every keyword.
+fn unset_keyword(input: &str) -> IResult<&str, Vec<Keyword>> {
+    map_opt(ident, Keyword::unset).parse(input)
+}
+
+/// A `keyword=value` pair.
+fn assign<'a, O>(
+    name: &'static str,
+    value: impl Parser<&'a str, Output = O, Error = nom::error::Error<&'a str>>,
+) -> impl Parser<&'a str, Output = O, Error = nom::error::Error<&'a str>> {
+    preceded((keyword(name), char('=')), value)
+}
+
+/// A hex digest with its keyword or the short alias of it.
+fn digest<'a>(
+    name: &'static str,
+    alias: &'static str,
+) -> impl Parser<&'a str, Output = &'a str, Error = nom::error::Error<&'a str>> {
+    preceded(
+        (alt((keyword(name), keyword(alias))), char('=')),
+        digits(16),
+    )
+}
+
+/// A property surrounded by any whitespace, including newlines.
+fn padded<'a, O>(
+    parser: impl Parser<&'a str, Output = O, Error = nom::error::Error<&'a str>>,
+) -> impl Parser<&'a str, Output = O, Error = nom::error::Error<&'a str>> {
+    delimited(
+        take_while(char::is_whitespace),
+        parser,
+        take_while(char::is_whitespace),
+    )
+}
+
+/// An ASCII identifier that is exactly the given keyword.
+fn keyword<'a>(
+    name: &'static str,
+) -> impl Parser<&'a str, Output = &'a str, Error = nom::error::Error<&'a str>> {
+    verify(ident, move |ident: &str| ident == name)
+}
+
+/// An ASCII identifier.
+fn ident(input: &str) -> IResult<&str, &str> {
+    recognize((
+        satisfy(|c| c.is_ascii_alphabetic() || c == '_'),
+        take_while(|c: char| c.is_ascii_alphanumeric() || c == '_'),
+    ))
+    .parse(input)
+}
+
+/// One or more digits of the given radix.
+fn digits<'a>(
+    radix: u32,
+) -> impl Parser<&'a str, Output = &'a str, Error = nom::error::Error<&'a str>> {
+    take_while1(move |c: char| c.is_digit(radix))
+}
+
+/// A decimal integer without leading zeros.
+fn int(input: &str) -> IResult<&str, &str> {
+    alt((
+        recognize((
+            satisfy(|c| c.is_ascii_digit() && c != '0'),
+            take_while(|c: char| c.is_ascii_digit()),
+        )),
+        tag("0"),
+    ))
+    .parse(input)
+}
+
+/// A line break, which may also be one of the unicode line separators.
+fn newline(input: &str) -> IResult<&str, ()> {
+    value(
+        (),
+        alt((
+            recognize((opt(char('\r')), char('\n'))),
+            recognize(satisfy(|c| {
+                matches!(
+                    c,
+                    '\r' | '\x0B' | '\x0C' | '\u{0085}' | '\u{2028}' | '\u{2029}'
+                )
+            })),
+        )),
+    )
+    .parse(input)
+}
+
+fn mode(input: &str) -> IResult<&str, Mode> {
+    map_res(digits(8), Mode::from_octal).parse(input)
+}
+
+fn id(input: &str) -> IResult<&str, u32> {
+    map_res(digits(10), str::parse).parse(input)
+}
+
+/// A plain keyword value, which spans until the next whitespace.
+fn value_str(input: &str) -> IResult<&str, &str> {
+    take_while1(|c| !matches!(c, ' ' | '\t' | '\n')).parse(input)
+}
+
+fn timestamp(input: &str) -> IResult<&str, Timestamp> {
+    map_res(
+        recognize((opt(char('-')), digits(10), opt((char('.'), digits(10))))),
+        str::parse,
+    )
+    .parse(input)
+}
+
+fn path_type(input: &str) -> IResult<&str, PathType> {
+    alt((
+        value(PathType::Block, keyword("block")),
+        value(PathType::Char, keyword("char")),
+        value(PathType::Dir, keyword("dir")),
+        value(PathType::Fifo, keyword("fifo")),
+        value(PathType::File, keyword("file")),
+        value(PathType::Link, keyword("link")),
+        value(PathType::Socket, keyword("socket")),
+    ))
+    .parse(input)
+}
+
+fn device(input: &str) -> IResult<&str, Device> {
+    map_res(value_str, str::parse).parse(input)
+}
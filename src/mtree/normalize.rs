@@ -0,0 +1,78 @@
+use std::borrow::Cow;
+
+use super::{export::Column, DigestAlgorithm, Entry, Gid, Uid};
+
+/// How [normalize] rewrites entries, so that manifests of the same files compare equal even if
+/// they were generated differently, e.g. at another time or by another user.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NormalizeOptions {
+    /// The properties to remove from every entry, e.g. [Column::Time] for reproducible builds.
+    /// [Column::Path] is ignored, an entry always keeps its path.
+    pub strip: Vec<Column>,
+    /// Rewrite every uid and gid to 0, and every uname and gname to `root`, as if the files
+    /// were packaged by root. Entries without an owner don't get one.
+    pub root_owner: bool,
+    /// Only keep the digests of these algorithms, e.g. only MD5 to compare with a manifest that
+    /// doesn't have SHA256 digests. `None` keeps all digests.
+    pub digests: Option<Vec<DigestAlgorithm>>,
+}
+
+impl NormalizeOptions {
+    /// Rewrite a single entry.
+    pub fn apply(&self, entry: &mut Entry) {
+        for column in &self.strip {
+            strip(entry, *column);
+        }
+        if self.root_owner {
+            entry.uid = entry.uid.map(|_| Uid(0));
+            entry.gid = entry.gid.map(|_| Gid(0));
+            entry.uname = entry.uname.as_ref().map(|_| Cow::Borrowed("root"));
+            entry.gname = entry.gname.as_ref().map(|_| Cow::Borrowed("root"));
+        }
+        if let Some(digests) = &self.digests {
+            for algorithm in DigestAlgorithm::ALL {
+                if !digests.contains(algorithm) {
+                    strip(entry, digest_column(*algorithm));
+                }
+            }
+        }
+    }
+}
+
+/// Rewrite all entries as told by the options, see [NormalizeOptions].
+pub fn normalize(entries: &mut [Entry], options: &NormalizeOptions) {
+    for entry in entries {
+        options.apply(entry);
+    }
+}
+
+/// The property that records the digests of an algorithm.
+fn digest_column(algorithm: DigestAlgorithm) -> Column {
+    match algorithm {
+        DigestAlgorithm::Md5 => Column::Md5Digest,
+        DigestAlgorithm::Sha1 => Column::Sha1Digest,
+        DigestAlgorithm::Sha256 => Column::Sha256Digest,
+    }
+}
+
+/// Remove a property from an entry.
+fn strip(entry: &mut Entry, column: Column) {
+    match column {
+        Column::Path => {}
+        Column::Type => entry.path_type = None,
+        Column::Uid => entry.uid = None,
+        Column::Gid => entry.gid = None,
+        Column::Uname => entry.uname = None,
+        Column::Gname => entry.gname = None,
+        Column::Mode => entry.mode = None,
+        Column::Size => entry.size = None,
+        Column::Nlink => entry.nlink = None,
+        Column::Link => entry.link = None,
+        Column::Md5Digest => entry.md5digest = None,
+        Column::Sha1Digest => entry.sha1digest = None,
+        Column::Sha256Digest => entry.sha256digest = None,
+        Column::Time => entry.time = None,
+        Column::Device => entry.device = None,
+        Column::ResDevice => entry.resdevice = None,
+    }
+}
@@ -0,0 +1,313 @@
+use std::{collections::HashSet, fmt, str::FromStr};
+
+use anyhow::bail;
+
+use super::{
+    handrolled, resolve_with, vis, Backend, Diagnostic, DuplicatePolicy, Keyword, Limits,
+    MtreeBackend, Resolved, Severity, Statement,
+};
+
+/// The keywords of a path, a `/set` or a `/unset` line.
+const KEYWORDS: &[&str] = &[
+    "uid",
+    "gid",
+    "uname",
+    "gname",
+    "mode",
+    "type",
+    "size",
+    "nlink",
+    "link",
+    "md5",
+    "md5digest",
+    "sha1",
+    "sha1digest",
+    "sha256",
+    "sha256digest",
+    "time",
+    "device",
+    "resdevice",
+];
+
+/// How forgiving the parser is about manifests that don't quite follow the format.
+///
+/// This covers unknown keywords, a missing `#mtree` header, duplicate paths and malformed
+/// timestamps. Any other syntax error is always a hard error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Strictness {
+    /// Every deviation is an error.
+    Strict,
+    /// Deviations are reported as warnings, the offending keywords are skipped.
+    #[default]
+    Default,
+    /// Deviations are silently ignored, the offending keywords are skipped.
+    Lenient,
+}
+
+impl Strictness {
+    pub const ALL: &'static [Strictness] = &[Self::Strict, Self::Default, Self::Lenient];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Strict => "strict",
+            Self::Default => "default",
+            Self::Lenient => "lenient",
+        }
+    }
+}
+
+impl FromStr for Strictness {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match Self::ALL.iter().find(|strictness| strictness.as_str() == s) {
+            Some(strictness) => Ok(*strictness),
+            None => bail!("unknown strictness: {s}"),
+        }
+    }
+}
+
+impl fmt::Display for Strictness {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Options for parsing a manifest with [ParseOptions::parse].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ParseOptions {
+    /// The parser implementation to use.
+    pub backend: Backend,
+    pub strictness: Strictness,
+    /// Caps on the size of the manifest, which are always hard errors.
+    pub limits: Limits,
+}
+
+/// The result of a successful parse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Parsed<'a> {
+    pub statements: Vec<Statement<'a>>,
+    /// Warnings about the deviations that were tolerated, only reported with
+    /// [Strictness::Default].
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ParseOptions {
+    /// Parse a whole manifest with the configured strictness.
+    ///
+    /// The backend parses the manifest first. Only if that fails, and the strictness allows
+    /// it, the manifest is parsed again line by line, skipping unknown keywords and malformed
+    /// timestamps.
+    #[tracing::instrument(skip_all, fields(bytes = input.len(), backend = ?self.backend, strictness = ?self.strictness))]
+    pub fn parse<'a>(&self, input: &'a str) -> Result<Parsed<'a>, Diagnostic> {
+        self.limits.check_input(input)?;
+        let mut diagnostics = Vec::new();
+        let parsed = match self.backend {
+            // The handrolled parser yields one statement at a time, so a manifest over the
+            // limits is rejected without parsing all of it.
+            Backend::HandRolled => self.limits.collect(handrolled::Statements::new(input))?,
+            backend => backend.parse(input),
+        };
+        let statements = match parsed {
+            Ok(statements) => statements,
+            Err(err) if self.strictness == Strictness::Strict => return Err(err.into()),
+            Err(err) => {
+                tracing::debug!(error = %err, "backend failed, recovering line by line");
+                self.recover(input, &mut diagnostics)?
+            }
+        };
+        tracing::debug!(statements = statements.len(), "parsed manifest");
+        self.limits.check_statements(&statements)?;
+
+        if !matches!(statements.first(), Some(Statement::Init)) {
+            let diagnostic =
+                Diagnostic::warning("missing-header", "missing `#mtree` header").with_span(0..0);
+            self.report(diagnostic, &mut diagnostics)?;
+        }
+        self.check_duplicates(input, &mut diagnostics)?;
+
+        Ok(Parsed {
+            statements,
+            diagnostics,
+        })
+    }
+
+    /// Parse a manifest and resolve its entries, with the policy for paths that show up more
+    /// than once, see [resolve_with].
+    ///
+    /// The duplicate paths are reported by the resolution, with what the policy did about them,
+    /// instead of by the parser.
+    pub fn resolve<'a>(
+        &self,
+        input: &'a str,
+        policy: DuplicatePolicy,
+    ) -> Result<Resolved<'a>, Diagnostic> {
+        let parsed = self.parse(input)?;
+        let resolved = resolve_with(&parsed.statements, policy)?;
+
+        Ok(Resolved {
+            entries: resolved.entries,
+            diagnostics: parsed
+                .diagnostics
+                .into_iter()
+                .filter(|diagnostic| diagnostic.code != "duplicate-path")
+                .chain(resolved.diagnostics)
+                .collect(),
+        })
+    }
+
+    /// Handle a deviation according to the strictness.
+    fn report(
+        &self,
+        diagnostic: Diagnostic,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Result<(), Diagnostic> {
+        match self.strictness {
+            Strictness::Strict => Err(Diagnostic {
+                severity: Severity::Error,
+                ..diagnostic
+            }),
+            Strictness::Default => {
+                diagnostics.push(diagnostic);
+                Ok(())
+            }
+            Strictness::Lenient => Ok(()),
+        }
+    }
+
+    /// Parse the manifest line by line and skip the keywords that don't parse.
+    fn recover<'a>(
+        &self,
+        input: &'a str,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Result<Vec<Statement<'a>>, Diagnostic> {
+        let mut statements = Vec::new();
+        for line in input.split_inclusive('\n') {
+            let err = match self.backend.parse(line) {
+                Ok(parsed) => {
+                    for statement in parsed {
+                        self.limits
+                            .check_statement(statements.len() + 1, &statement)?;
+                        statements.push(statement);
+                    }
+                    continue;
+                }
+                Err(err) => err,
+            };
+
+            let mut tokens = line
+                .split([' ', '\t', '\n'])
+                .filter(|token| !token.is_empty());
+            let Some(head) = tokens.next() else {
+                continue;
+            };
+            let statement = if head.starts_with('.') {
+                let mut properties = Vec::new();
+                for token in tokens {
+                    match handrolled::property(token) {
+                        Some(property) => properties.push(property),
+                        None => self.skip(input, token, KEYWORDS, diagnostics)?,
+                    }
+                }
+                Statement::Path {
+                    path: vis::decode(head),
+                    properties,
+                }
+            } else if head == "/set" {
+                let mut properties = Vec::new();
+                for token in tokens {
+                    match handrolled::property(token) {
+                        Some(property) => properties.push(property),
+                        None => self.skip(input, token, KEYWORDS, diagnostics)?,
+                    }
+                }
+                Statement::Set(properties)
+            } else if head == "/unset" {
+                let mut keywords = Vec::new();
+                for token in tokens {
+                    match Keyword::unset(token) {
+                        Some(unset) => keywords.extend(unset),
+                        None => self.skip(input, token, KEYWORDS, diagnostics)?,
+                    }
+                }
+                Statement::Unset(keywords)
+            } else {
+                let start = offset(input, line);
+                return Err(Diagnostic::error("syntax", err.message)
+                    .with_span(start + err.span.start..start + err.span.end));
+            };
+            self.limits
+                .check_statement(statements.len() + 1, &statement)?;
+            statements.push(statement);
+        }
+
+        Ok(statements)
+    }
+
+    /// Skip a `keyword=value` token that doesn't parse, if it has an unknown keyword or
+    /// a malformed timestamp.
+    fn skip(
+        &self,
+        input: &str,
+        token: &str,
+        keywords: &[&str],
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Result<(), Diagnostic> {
+        let start = offset(input, token);
+        let span = start..start + token.len();
+        let diagnostic = match token.split_once('=') {
+            Some((keyword, _)) if !keywords.contains(&keyword) => {
+                Diagnostic::warning("unknown-keyword", format!("unknown keyword `{keyword}`"))
+            }
+            None if !keywords.contains(&token) => {
+                Diagnostic::warning("unknown-keyword", format!("unknown keyword `{token}`"))
+            }
+            Some(("time", value)) => Diagnostic::warning(
+                "malformed-timestamp",
+                format!("malformed timestamp `{value}`"),
+            ),
+            _ => {
+                return Err(
+                    Diagnostic::error("syntax", format!("invalid property `{token}`"))
+                        .with_span(span),
+                )
+            }
+        };
+
+        self.report(diagnostic.with_span(span), diagnostics)
+    }
+
+    /// Report every path that occurs more than once.
+    fn check_duplicates(
+        &self,
+        input: &str,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Result<(), Diagnostic> {
+        let mut paths = HashSet::new();
+        for line in input.lines().map(str::trim_start) {
+            let Some(path) = line
+                .split([' ', '\t'])
+                .next()
+                .filter(|p| p.starts_with('.'))
+            else {
+                continue;
+            };
+            if !paths.insert(vis::decode(path)) {
+                let start = offset(input, path);
+                let diagnostic = Diagnostic::warning(
+                    "duplicate-path",
+                    format!("duplicate path `{}`", vis::decode(path)),
+                )
+                .with_span(start..start + path.len());
+                self.report(diagnostic, diagnostics)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The byte offset of a subslice in the input.
+fn offset(input: &str, slice: &str) -> usize {
+    slice.as_ptr() as usize - input.as_ptr() as usize
+}
@@ -0,0 +1,195 @@
+use std::borrow::Cow;
+
+use chumsky::{prelude::*, text::ascii, Parser};
+use text::newline;
+
+use super::{vis, Device, Gid, Keyword, Mode, PathType, Property, Statement, Timestamp, Uid};
+
+pub fn parser<'a>() -> impl Parser<'a, &'a str, Vec<Statement<'a>>, extra::Err<Rich<'a, char>>> {
+    use Statement::*;
+
+    // Parser for the very first line of the `.MTREE` file
+    let mtree = just("#")
+        .then(ascii::keyword("mtree"))
+        .then_ignore(newline())
+        .to(Init);
+
+    // Parser for the properties behind a path line or a `/set` command
+    let properties = choice((
+        // `uid` and `gid` parser that expect a user/group id.
+        ascii::keyword("uid")
+            .then(just('='))
+            .ignore_then(id())
+            .map(|id| Property::Uid(Uid(id))),
+        ascii::keyword("gid")
+            .then(just('='))
+            .ignore_then(id())
+            .map(|id| Property::Gid(Gid(id))),
+        // `uname` and `gname` parser that expect a user/group name.
+        ascii::keyword("uname")
+            .then(just('='))
+            .ignore_then(escaped())
+            .map(Property::Uname),
+        ascii::keyword("gname")
+            .then(just('='))
+            .ignore_then(escaped())
+            .map(Property::Gname),
+        // `mode` parser which expects some octal digits
+        ascii::keyword("mode")
+            .then(just('='))
+            .ignore_then(mode())
+            .map(Property::Mode),
+        // Digest parsers which expect a hex digest.
+        // Each digest keyword also has a short alias without the `digest` suffix.
+        choice((ascii::keyword("md5digest"), ascii::keyword("md5")))
+            .then(just('='))
+            .ignore_then(text::digits(16).to_slice())
+            .map(|s: &str| Property::Md5Digest(s)),
+        choice((ascii::keyword("sha1digest"), ascii::keyword("sha1")))
+            .then(just('='))
+            .ignore_then(text::digits(16).to_slice())
+            .map(|s: &str| Property::Sha1Digest(s)),
+        choice((ascii::keyword("sha256digest"), ascii::keyword("sha256")))
+            .then(just('='))
+            .ignore_then(text::digits(16).to_slice())
+            .map(|s: &str| Property::Sha256Digest(s)),
+        // `size` parser which expects a decimal filesize in bytes
+        ascii::keyword("size")
+            .then(just('='))
+            .ignore_then(text::digits(10).to_slice())
+            .try_map(|s: &str, span| s.parse().map_err(|e| Rich::custom(span, e)))
+            .map(Property::Size),
+        // `nlink` parser which expects the decimal number of hard links to a file
+        ascii::keyword("nlink")
+            .then(just('='))
+            .ignore_then(text::int(10).to_slice())
+            .try_map(|s: &str, span| s.parse().map_err(|e| Rich::custom(span, e)))
+            .map(Property::Nlink),
+        // `time` parser which expects a decimal epoch.
+        // For some reason, this is a floating point number.
+        // The decimal places are kept with nanosecond precision.
+        ascii::keyword("time")
+            .then(just('='))
+            .ignore_then(timestamp())
+            .map(Property::Time),
+        // `type` parser which can be one of `file`, `dir`, `link` or any of the special types.
+        ascii::keyword("type")
+            .then(just('='))
+            .ignore_then(path_type())
+            .map(Property::Type),
+        // `link` parser, which defines what a link links to.
+        ascii::keyword("link")
+            .then(just('='))
+            .ignore_then(escaped())
+            .map(Property::Link),
+        // `device` and `resdevice` parser, which expect a `format,major,minor` triple.
+        ascii::keyword("device")
+            .then(just('='))
+            .ignore_then(device())
+            .map(Property::Device),
+        ascii::keyword("resdevice")
+            .then(just('='))
+            .ignore_then(device())
+            .map(Property::ResDevice),
+    ))
+    .padded()
+    .repeated()
+    .collect::<Vec<_>>()
+    .boxed();
+
+    // The `/set` parser
+    // Afterwards follows a whitespace delimited list of properties, the same as for a path.
+    let set = just("/")
+        .then(ascii::keyword("set"))
+        .ignore_then(properties.clone())
+        .map(Set);
+
+    // The `/unset` parser
+    // Afterwards follows a whitespace delimited list of keywords without values, or `all`.
+    let unset = just("/")
+        .then(ascii::keyword("unset"))
+        .ignore_then(
+            ascii::ident()
+                .try_map(|word: &str, span| {
+                    Keyword::unset(word)
+                        .ok_or_else(|| Rich::custom(span, format!("unknown keyword `{word}`")))
+                })
+                .padded()
+                .repeated()
+                .collect::<Vec<_>>(),
+        )
+        .map(|keywords| Unset(keywords.concat()));
+
+    // Parse a path line.
+    // It starts with a `.` followed by some text, delimited by a whitespace.
+    // Whitespaces inside the path are vis-encoded, e.g. `\040` for a space.
+    // Afterwards follows a whitespace delimited list of properties.
+    let path = just(".")
+        .then(none_of(" \t\n").repeated())
+        .to_slice()
+        .map(vis::decode)
+        .then(properties)
+        .map(|(path, properties)| Path { path, properties });
+
+    recursive(|_| {
+        choice((mtree, set, unset, path))
+            .then_ignore(text::whitespace())
+            .repeated()
+            .collect()
+    })
+}
+
+/// Parser for the octal digits of a `mode=` value.
+/// This includes the 4-digit forms that set the setuid, setgid or sticky bit.
+fn mode<'a>() -> impl Parser<'a, &'a str, Mode, extra::Err<Rich<'a, char>>> + Clone {
+    text::digits(8)
+        .to_slice()
+        .try_map(|s: &str, span| Mode::from_octal(s).map_err(|e| Rich::custom(span, e)))
+}
+
+/// Parser for a decimal user or group id.
+fn id<'a>() -> impl Parser<'a, &'a str, u32, extra::Err<Rich<'a, char>>> + Clone {
+    text::digits(10)
+        .to_slice()
+        .try_map(|s: &str, span| s.parse().map_err(|e| Rich::custom(span, e)))
+}
+
+/// Parser for a plain keyword value, which spans until the next whitespace.
+fn value<'a>() -> impl Parser<'a, &'a str, &'a str, extra::Err<Rich<'a, char>>> + Clone {
+    none_of(" \t\n").repeated().at_least(1).to_slice()
+}
+
+/// Parser for a free-form value like a name or a link target, which may contain vis escapes
+/// just like a path.
+fn escaped<'a>() -> impl Parser<'a, &'a str, Cow<'a, str>, extra::Err<Rich<'a, char>>> + Clone {
+    value().map(vis::decode)
+}
+
+/// Parser for a `time=` value in the form of `seconds.fraction`.
+/// The seconds may be negative for timestamps before 1970.
+fn timestamp<'a>() -> impl Parser<'a, &'a str, Timestamp, extra::Err<Rich<'a, char>>> + Clone {
+    just('-')
+        .or_not()
+        .then(text::digits(10))
+        .then(just('.').then(text::digits(10)).or_not())
+        .to_slice()
+        .try_map(|s: &str, span| s.parse().map_err(|e| Rich::custom(span, e)))
+}
+
+/// Parser for the value of a `type=` keyword.
+fn path_type<'a>() -> impl Parser<'a, &'a str, PathType, extra::Err<Rich<'a, char>>> + Clone {
+    choice((
+        ascii::keyword("block").to(PathType::Block),
+        ascii::keyword("char").to(PathType::Char),
+        ascii::keyword("dir").to(PathType::Dir),
+        ascii::keyword("fifo").to(PathType::Fifo),
+        ascii::keyword("file").to(PathType::File),
+        ascii::keyword("link").to(PathType::Link),
+        ascii::keyword("socket").to(PathType::Socket),
+    ))
+}
+
+/// Parser for a `device=` value, e.g. `linux,1,3`.
+fn device<'a>() -> impl Parser<'a, &'a str, Device, extra::Err<Rich<'a, char>>> + Clone {
+    value().try_map(|s: &str, span| s.parse().map_err(|e| Rich::custom(span, e)))
+}
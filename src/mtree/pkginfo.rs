@@ -0,0 +1,131 @@
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use anyhow::bail;
+
+use super::Diagnostic;
+
+/// The keys of a `.PKGINFO` or `.BUILDINFO` with all their values, as parsed by [parse_info].
+pub type Info<'a> = BTreeMap<&'a str, Vec<&'a str>>;
+
+/// How the key and the value of a line are separated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Separator {
+    /// `key = value`, with or without spaces around the `=`, like `.PKGINFO` and `.BUILDINFO`.
+    #[default]
+    Equals,
+    /// `key: value`.
+    Colon,
+    /// `key value`, the key ends at the first whitespace.
+    Whitespace,
+}
+
+impl Separator {
+    pub const ALL: &'static [Separator] = &[Self::Equals, Self::Colon, Self::Whitespace];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Equals => "equals",
+            Self::Colon => "colon",
+            Self::Whitespace => "whitespace",
+        }
+    }
+
+    /// Split a line into its trimmed key and its value as is.
+    fn split(self, line: &str) -> Option<(&str, &str)> {
+        let (key, value) = match self {
+            Self::Equals => line.split_once('=')?,
+            Self::Colon => line.split_once(':')?,
+            Self::Whitespace => line.split_once(char::is_whitespace)?,
+        };
+        Some((key.trim(), value))
+    }
+
+    /// How a line looks, for errors.
+    fn example(self) -> &'static str {
+        match self {
+            Self::Equals => "key = value",
+            Self::Colon => "key: value",
+            Self::Whitespace => "key value",
+        }
+    }
+}
+
+impl fmt::Display for Separator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Separator {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match Self::ALL.iter().find(|separator| separator.as_str() == s) {
+            Some(separator) => Ok(*separator),
+            None => bail!("unknown separator: {s}"),
+        }
+    }
+}
+
+/// Options for parsing the lines of a file with [InfoOptions::parse].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct InfoOptions {
+    pub separator: Separator,
+    /// Strip ` # comment` and ` ; comment` suffixes from values, as in hand-edited files.
+    /// A `#` or `;` in quotes, or without whitespace before it, is part of the value.
+    pub inline_comments: bool,
+}
+
+impl InfoOptions {
+    /// Parse the lines of a file into its keys and their values, see [parse_info].
+    pub fn parse<'a>(&self, content: &'a str) -> Result<Info<'a>, Diagnostic> {
+        let mut info = Info::new();
+        let mut offset = 0;
+        for line in content.split_inclusive('\n') {
+            let span = offset..offset + line.trim_end().len();
+            offset += line.len();
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let Some((key, mut value)) = self.separator.split(line) else {
+                let message = format!("expected a `{}` line", self.separator.example());
+                return Err(Diagnostic::error("invalid-line", message).with_span(span));
+            };
+            if self.inline_comments {
+                // The whitespace between the key and the value comes before the value as well.
+                value = strip_comment(value, self.separator == Separator::Whitespace);
+            }
+            info.entry(key).or_default().push(value.trim());
+        }
+
+        Ok(info)
+    }
+}
+
+/// Parse the `key = value` lines of a `.PKGINFO` or `.BUILDINFO`.
+///
+/// Keys that can show up multiple times, like `depend`, have all their values in the order of
+/// the file. Comments and blank lines are skipped, a line without `=` is an error.
+pub fn parse_info(content: &str) -> Result<Info<'_>, Diagnostic> {
+    InfoOptions::default().parse(content)
+}
+
+/// Cut a value at the first `#` or `;` that follows whitespace and isn't quoted.
+fn strip_comment(value: &str, after_space: bool) -> &str {
+    let mut quote = None;
+    let mut after_space = after_space;
+    for (index, c) in value.char_indices() {
+        match (quote, c) {
+            (Some(open), _) if c == open => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '#' | ';') if after_space => return &value[..index],
+            _ => {}
+        }
+        after_space = c.is_whitespace();
+    }
+
+    value
+}
@@ -0,0 +1,24 @@
+use std::fmt;
+
+/// Receives the progress of long operations over many files, e.g. to show a progress bar.
+///
+/// The methods are called from multiple threads. `()` ignores all updates.
+pub trait Progress: Send + Sync {
+    /// The operation starts with this many files of this many bytes in total.
+    /// The number of bytes is `0` if it isn't known in advance.
+    fn start(&self, _files: u64, _bytes: u64) {}
+
+    /// A file of `bytes` bytes is done.
+    fn advance(&self, _bytes: u64) {}
+
+    /// The operation is done.
+    fn finish(&self) {}
+}
+
+impl Progress for () {}
+
+impl fmt::Debug for dyn Progress + '_ {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Progress")
+    }
+}
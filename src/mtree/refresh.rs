@@ -0,0 +1,107 @@
+use std::{fs, io, path::Path, sync::Arc};
+
+use rayon::prelude::*;
+
+use super::{verify::fs_path, DigestAlgorithm, Entry, Excludes, PathType, Progress};
+
+/// Options for [refresh].
+#[derive(Debug, Clone, Default)]
+pub struct RefreshOptions {
+    /// The digests to compute for every file. If empty, each file only gets the digests that
+    /// it already records.
+    pub digests: Vec<DigestAlgorithm>,
+    /// The number of threads to hash files with, `0` uses one thread per CPU.
+    pub threads: usize,
+    /// Paths that are left as they are.
+    pub excludes: Option<Excludes>,
+    /// Receives every hashed file with its size.
+    pub progress: Option<Arc<dyn Progress>>,
+}
+
+/// Recompute the sizes and digests of the files of a manifest from the files below `root`,
+/// e.g. after a post-processing step like stripping binaries touched them.
+///
+/// Only regular files are updated, all other properties stay as they are. A file that doesn't
+/// exist anymore is an error, since its entry can't be refreshed. Files are hashed in parallel
+/// on a dedicated thread pool. Returns the number of entries that changed.
+#[tracing::instrument(skip(entries, options), fields(root = %root.display(), digests = ?options.digests))]
+pub fn refresh(entries: &mut [Entry], root: &Path, options: &RefreshOptions) -> io::Result<usize> {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(options.threads)
+        .build()
+        .map_err(io::Error::other)?;
+    let progress = options.progress.as_deref().unwrap_or(&());
+
+    let mut files = entries
+        .iter_mut()
+        .filter(|entry| matches!(entry.path_type, Some(PathType::File) | None))
+        .filter(|entry| {
+            options
+                .excludes
+                .as_ref()
+                .is_none_or(|excludes| !excludes.excludes(entry))
+        })
+        .collect::<Vec<_>>();
+    let bytes = files
+        .iter()
+        .map(|entry| entry.size.unwrap_or(0) as u64)
+        .sum();
+    tracing::info!(
+        files = files.len(),
+        threads = pool.current_num_threads(),
+        "refreshing files"
+    );
+
+    progress.start(files.len() as u64, bytes);
+    let changed = pool.install(|| {
+        files
+            .par_iter_mut()
+            .map(|entry| {
+                let changed = refresh_entry(entry, root, &options.digests);
+                progress.advance(entry.size.unwrap_or(0) as u64);
+                changed
+            })
+            .try_fold(
+                || 0,
+                |count, changed| Ok::<_, io::Error>(count + changed? as usize),
+            )
+            .try_reduce(|| 0, |a, b| Ok(a + b))
+    });
+    progress.finish();
+    let changed = changed?;
+    tracing::info!(changed, "refreshed entries");
+
+    Ok(changed)
+}
+
+/// Recompute the size and the digests of a single file, returns whether anything changed.
+fn refresh_entry(entry: &mut Entry, root: &Path, digests: &[DigestAlgorithm]) -> io::Result<bool> {
+    let context = |err: io::Error| io::Error::new(err.kind(), format!("{}: {err}", entry.path));
+    let path = fs_path(root, &entry.path)?;
+    let metadata = fs::symlink_metadata(&path).map_err(context)?;
+    if metadata.is_dir() || metadata.is_symlink() {
+        // An entry without a type doesn't have to be a file.
+        return Ok(false);
+    }
+
+    let algorithms = if digests.is_empty() {
+        DigestAlgorithm::ALL
+            .iter()
+            .copied()
+            .filter(|algorithm| algorithm.expected(entry).is_some())
+            .collect()
+    } else {
+        digests.to_vec()
+    };
+    let hashed = super::digest::hash_file(&path, &algorithms).map_err(context)?;
+
+    let size = usize::try_from(metadata.len()).ok();
+    let mut changed = entry.size != size;
+    entry.size = size;
+    for (algorithm, digest) in hashed {
+        changed |= algorithm.expected(entry) != Some(digest.as_str());
+        algorithm.set(entry, digest);
+    }
+
+    Ok(changed)
+}
@@ -0,0 +1,105 @@
+use std::{collections::BTreeSet, io::Write, ops::Range};
+
+use anyhow::Result;
+use serde_json::{json, Value};
+
+use super::{Diagnostic, Severity};
+
+/// The schema of the SARIF version that is written.
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// The diagnostics of a single manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Artifact<'a> {
+    /// The path of the manifest as it should appear in the report.
+    pub uri: &'a str,
+    /// The content of the manifest, to turn the spans into lines and columns.
+    pub source: &'a str,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Write the diagnostics of manifests as a SARIF 2.1.0 log, which code scanning services
+/// like GitHub's can ingest. Every diagnostic code is a rule of the tool.
+pub fn write<W: Write>(writer: W, artifacts: &[Artifact]) -> Result<()> {
+    let rules = artifacts
+        .iter()
+        .flat_map(|artifact| &artifact.diagnostics)
+        .map(|diagnostic| diagnostic.code)
+        .collect::<BTreeSet<_>>();
+    let results = artifacts
+        .iter()
+        .flat_map(|artifact| {
+            artifact
+                .diagnostics
+                .iter()
+                .map(|diagnostic| result(artifact, diagnostic))
+        })
+        .collect::<Vec<_>>();
+
+    let log = json!({
+        "$schema": SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules.iter().map(|id| json!({ "id": id })).collect::<Vec<_>>(),
+                }
+            },
+            "columnKind": "unicodeCodePoints",
+            "results": results,
+        }]
+    });
+    serde_json::to_writer_pretty(writer, &log)?;
+
+    Ok(())
+}
+
+/// A single result of the log.
+fn result(artifact: &Artifact, diagnostic: &Diagnostic) -> Value {
+    let level = match diagnostic.severity {
+        Severity::Info => "note",
+        Severity::Warning => "warning",
+        Severity::Error => "error",
+    };
+    let mut text = diagnostic.message.clone();
+    for note in &diagnostic.notes {
+        text.push('\n');
+        text.push_str(note);
+    }
+    let mut location = json!({
+        "physicalLocation": {
+            "artifactLocation": { "uri": artifact.uri },
+        }
+    });
+    if let Some(span) = &diagnostic.span {
+        location["physicalLocation"]["region"] = region(artifact.source, span);
+    }
+
+    json!({
+        "ruleId": diagnostic.code,
+        "level": level,
+        "message": { "text": text },
+        "locations": [location],
+    })
+}
+
+/// The 1-based lines and columns of a span, columns count characters.
+fn region(source: &str, span: &Range<usize>) -> Value {
+    let position = |offset: usize| {
+        let before = &source[..offset.min(source.len())];
+        let line = before.matches('\n').count() + 1;
+        let start = before.rfind('\n').map_or(0, |newline| newline + 1);
+        (line, before[start..].chars().count() + 1)
+    };
+    let (start_line, start_column) = position(span.start);
+    let (end_line, end_column) = position(span.end);
+
+    json!({
+        "startLine": start_line,
+        "startColumn": start_column,
+        "endLine": end_line,
+        "endColumn": end_column,
+    })
+}
@@ -0,0 +1,218 @@
+use std::{io::Write, time::SystemTime};
+
+use anyhow::Result;
+use serde_json::json;
+
+use super::{pkginfo::Info, Entry, PathType, Timestamp};
+
+/// Write a package as an SPDX 2.3 document, with its metadata and the files of its manifest.
+///
+/// SPDX asks for a SHA1 of every file, which ALPM packages don't record, so the files only have
+/// the digests that the manifest has.
+pub fn write_spdx<W: Write>(writer: W, info: &Info, entries: &[Entry]) -> Result<()> {
+    let package = Package::new(info);
+    let files = files(entries)
+        .enumerate()
+        .map(|(index, entry)| {
+            let mut checksums = Vec::new();
+            if let Some(digest) = &entry.sha256digest {
+                checksums.push(json!({ "algorithm": "SHA256", "checksumValue": digest }));
+            }
+            if let Some(digest) = &entry.md5digest {
+                checksums.push(json!({ "algorithm": "MD5", "checksumValue": digest }));
+            }
+            json!({
+                "fileName": entry.path,
+                "SPDXID": format!("SPDXRef-File-{index}"),
+                "checksums": checksums,
+                "licenseConcluded": "NOASSERTION",
+                "copyrightText": "NOASSERTION",
+            })
+        })
+        .collect::<Vec<_>>();
+    let mut relationships = vec![json!({
+        "spdxElementId": "SPDXRef-DOCUMENT",
+        "relationshipType": "DESCRIBES",
+        "relatedSpdxElement": "SPDXRef-Package",
+    })];
+    relationships.extend(files.iter().map(|file| {
+        json!({
+            "spdxElementId": "SPDXRef-Package",
+            "relationshipType": "CONTAINS",
+            "relatedSpdxElement": file["SPDXID"],
+        })
+    }));
+
+    let mut spdx_package = json!({
+        "name": package.name,
+        "SPDXID": "SPDXRef-Package",
+        "versionInfo": package.version,
+        "downloadLocation": "NOASSERTION",
+        "filesAnalyzed": false,
+        "licenseConcluded": "NOASSERTION",
+        "licenseDeclared": package.license.as_deref().unwrap_or("NOASSERTION"),
+        "copyrightText": "NOASSERTION",
+        "externalRefs": [{
+            "referenceCategory": "PACKAGE-MANAGER",
+            "referenceType": "purl",
+            "referenceLocator": package.purl(),
+        }],
+    });
+    if let Some(description) = package.description {
+        spdx_package["description"] = json!(description);
+    }
+    if let Some(url) = package.url {
+        spdx_package["homepage"] = json!(url);
+    }
+    if let Some(packager) = package.packager {
+        spdx_package["supplier"] = json!(format!("Person: {packager}"));
+    }
+
+    let document = json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": format!("{}-{}", package.name, package.version),
+        "documentNamespace": format!(
+            "https://spdx.org/spdxdocs/{}-{}-{}",
+            package.name, package.version, package.arch
+        ),
+        "creationInfo": {
+            "created": package.created,
+            "creators": [format!("Tool: {}-{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))],
+        },
+        "packages": [spdx_package],
+        "files": files,
+        "relationships": relationships,
+    });
+    serde_json::to_writer_pretty(writer, &document)?;
+
+    Ok(())
+}
+
+/// Write a package as a CycloneDX 1.5 BOM, with its metadata and the files of its manifest.
+pub fn write_cyclonedx<W: Write>(writer: W, info: &Info, entries: &[Entry]) -> Result<()> {
+    let package = Package::new(info);
+    let files = files(entries)
+        .map(|entry| {
+            let mut hashes = Vec::new();
+            if let Some(digest) = &entry.sha256digest {
+                hashes.push(json!({ "alg": "SHA-256", "content": digest }));
+            }
+            if let Some(digest) = &entry.md5digest {
+                hashes.push(json!({ "alg": "MD5", "content": digest }));
+            }
+            json!({ "type": "file", "name": entry.path, "hashes": hashes })
+        })
+        .collect::<Vec<_>>();
+
+    let purl = package.purl();
+    let mut component = json!({
+        "type": "application",
+        "bom-ref": purl,
+        "name": package.name,
+        "version": package.version,
+        "purl": purl,
+    });
+    if let Some(description) = package.description {
+        component["description"] = json!(description);
+    }
+    if let Some(license) = &package.license {
+        component["licenses"] = json!([{ "expression": license }]);
+    }
+    if let Some(url) = package.url {
+        component["externalReferences"] = json!([{ "type": "website", "url": url }]);
+    }
+    if let Some(packager) = package.packager {
+        component["publisher"] = json!(packager);
+    }
+
+    let bom = json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "version": 1,
+        "metadata": {
+            "timestamp": package.created,
+            "tools": {
+                "components": [{
+                    "type": "application",
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                }],
+            },
+            "component": component,
+        },
+        "components": files,
+    });
+    serde_json::to_writer_pretty(writer, &bom)?;
+
+    Ok(())
+}
+
+/// The fields of a `.PKGINFO` that both formats use.
+struct Package<'a> {
+    name: &'a str,
+    version: &'a str,
+    arch: &'a str,
+    description: Option<&'a str>,
+    url: Option<&'a str>,
+    packager: Option<&'a str>,
+    /// The licenses as an SPDX expression.
+    license: Option<String>,
+    /// The build date, or now if the package doesn't tell.
+    created: String,
+}
+
+impl<'a> Package<'a> {
+    fn new(info: &Info<'a>) -> Self {
+        let field = |key| info.get(key).and_then(|values| values.first()).copied();
+        let created = field("builddate")
+            .and_then(|date| date.parse().ok())
+            .map_or_else(
+                || Timestamp::from(SystemTime::now()),
+                |secs| Timestamp::new(secs, 0),
+            );
+        let licenses = info.get("license").map(Vec::as_slice).unwrap_or_default();
+        let license = match licenses {
+            [] => None,
+            [license] => Some(license.to_string()),
+            // Each license of a package applies, compound expressions need parentheses.
+            licenses => Some(
+                licenses
+                    .iter()
+                    .map(|license| {
+                        if license.contains(' ') {
+                            format!("({license})")
+                        } else {
+                            license.to_string()
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" AND "),
+            ),
+        };
+
+        Self {
+            name: field("pkgname").unwrap_or("unknown"),
+            version: field("pkgver").unwrap_or("unknown"),
+            arch: field("arch").unwrap_or("any"),
+            description: field("pkgdesc"),
+            url: field("url"),
+            packager: field("packager"),
+            license,
+            created: created.to_rfc3339(),
+        }
+    }
+
+    /// The package URL, e.g. `pkg:alpm/zbar@0.23-1?arch=x86_64`.
+    fn purl(&self) -> String {
+        format!("pkg:alpm/{}@{}?arch={}", self.name, self.version, self.arch)
+    }
+}
+
+/// The regular files of a manifest, entries without a type are files as well.
+fn files<'e, 'a>(entries: &'e [Entry<'a>]) -> impl Iterator<Item = &'e Entry<'a>> {
+    entries
+        .iter()
+        .filter(|entry| matches!(entry.path_type, Some(PathType::File) | None))
+}
@@ -0,0 +1,121 @@
+use std::{fmt, io::Read};
+
+use pgp::{
+    composed::{Deserializable, DetachedSignature, SignedPublicKey},
+    types::KeyDetails,
+};
+use serde::Serialize;
+
+/// Errors that can occur when checking a detached signature.
+#[derive(Debug)]
+pub enum SignatureError {
+    /// The keyring or the signature isn't valid OpenPGP data.
+    Malformed(pgp::errors::Error),
+    /// None of the keys made the signature, or the data was altered after it was signed.
+    Invalid,
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Malformed(err) => write!(f, "{err}"),
+            Self::Invalid => write!(f, "the signature doesn't match any key of the keyring"),
+        }
+    }
+}
+
+impl std::error::Error for SignatureError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Malformed(err) => Some(err),
+            Self::Invalid => None,
+        }
+    }
+}
+
+impl From<pgp::errors::Error> for SignatureError {
+    fn from(err: pgp::errors::Error) -> Self {
+        Self::Malformed(err)
+    }
+}
+
+/// The public keys that signatures are checked against, e.g. as exported by `pacman-key`.
+#[derive(Debug, Clone, Default)]
+pub struct Keyring {
+    keys: Vec<SignedPublicKey>,
+}
+
+impl Keyring {
+    /// Read all keys of a keyring in the binary or the ASCII-armored format.
+    /// Keys whose self-signatures or subkey bindings don't verify are skipped.
+    pub fn read<R: Read>(reader: R) -> Result<Self, SignatureError> {
+        let (keys, _) = SignedPublicKey::from_reader_many(reader)?;
+        let mut keyring = Self::default();
+        for key in keys {
+            let key = key?;
+            match key.verify_bindings() {
+                Ok(()) => keyring.keys.push(key),
+                Err(err) => tracing::warn!(
+                    fingerprint = %format!("{:X}", key.fingerprint()),
+                    error = %err,
+                    "skipping key with invalid bindings"
+                ),
+            }
+        }
+
+        Ok(keyring)
+    }
+
+    /// The number of usable keys.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+/// The key that made a valid signature.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Signer {
+    /// The fingerprint of the primary key in upper case hex, as shown by `pacman-key`.
+    pub fingerprint: String,
+    /// The fingerprint of the subkey that made the signature, if it wasn't the primary key.
+    pub subkey: Option<String>,
+}
+
+/// Check a detached signature, e.g. a `.sig` next to a manifest or a database, against the
+/// data it signs, before the data is parsed.
+///
+/// The signature may be binary, like pacman writes them, or ASCII-armored. Returns the signer
+/// if a key of the keyring, or one of its subkeys, made the signature over exactly this data.
+pub fn verify_detached<R: Read>(
+    data: &[u8],
+    signature: R,
+    keyring: &Keyring,
+) -> Result<Signer, SignatureError> {
+    let (signature, _) = DetachedSignature::from_reader_single(signature)?;
+
+    for key in &keyring.keys {
+        let fingerprint = format!("{:X}", key.fingerprint());
+        if signature.verify(&key.primary_key, data).is_ok() {
+            return Ok(Signer {
+                fingerprint,
+                subkey: None,
+            });
+        }
+        if let Some(subkey) = key
+            .public_subkeys
+            .iter()
+            .find(|sub| signature.verify(&sub.key, data).is_ok())
+        {
+            return Ok(Signer {
+                fingerprint,
+                subkey: Some(format!("{:X}", subkey.key.fingerprint())),
+            });
+        }
+    }
+
+    Err(SignatureError::Invalid)
+}
@@ -0,0 +1,170 @@
+use std::{
+    fmt,
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+use super::encoding::{decode, Encoding};
+
+/// How many bytes of a file [sniff_file] looks at.
+const SNIFF_LEN: u64 = 4096;
+
+/// The compression of a stream, as told by its magic bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+    Xz,
+}
+
+impl Compression {
+    /// Detect the compression from the first bytes of a stream, `None` if it's uncompressed.
+    pub fn detect(magic: &[u8]) -> Option<Self> {
+        if magic.starts_with(&[0x1f, 0x8b]) {
+            Some(Self::Gzip)
+        } else if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Self::Zstd)
+        } else if magic.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(Self::Xz)
+        } else {
+            None
+        }
+    }
+
+    /// The compression that a file name implies, e.g. gzip for `.MTREE.gz`.
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "gz" => Some(Self::Gzip),
+            "zst" => Some(Self::Zstd),
+            "xz" => Some(Self::Xz),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Zstd => "zstd",
+            Self::Xz => "xz",
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The formats that [sniff] tells apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileFormat {
+    /// An mtree(5) manifest, with or without the `#mtree` header.
+    Mtree,
+    /// Entries as written by [write_json](super::export::write_json).
+    Json,
+    /// Entries as written by [write_yaml](super::export::write_yaml).
+    Yaml,
+    /// The `%NAME%` sections of a `desc` file in the pacman database.
+    Desc,
+    /// The `key = value` lines of a `.PKGINFO` or `.BUILDINFO`.
+    Info,
+    /// An INI file with `[section]` headers, like `pacman.conf`.
+    Ini,
+}
+
+impl FileFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Mtree => "mtree",
+            Self::Json => "json",
+            Self::Yaml => "yaml",
+            Self::Desc => "desc",
+            Self::Info => "pkginfo",
+            Self::Ini => "ini",
+        }
+    }
+}
+
+impl fmt::Display for FileFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Guess the format of a file from its first line that isn't blank or a comment.
+///
+/// The `#mtree` header is the only comment that counts. Returns `None` if the line doesn't
+/// look like any of the formats. The content has to be decompressed already, see [read].
+///
+/// [read]: super::read
+pub fn sniff(content: &str) -> Option<FileFormat> {
+    for line in content.lines().map(str::trim) {
+        if line.starts_with("#mtree") {
+            return Some(FileFormat::Mtree);
+        }
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        return sniff_line(line);
+    }
+
+    None
+}
+
+/// Guess the format of a file from its first significant line.
+fn sniff_line(line: &str) -> Option<FileFormat> {
+    let is_key = |key: &str| {
+        !key.is_empty()
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    };
+
+    if line.starts_with("/set") || line.starts_with("/unset") || line.starts_with('.') {
+        Some(FileFormat::Mtree)
+    } else if line.starts_with('{') {
+        Some(FileFormat::Json)
+    } else if let Some(rest) = line.strip_prefix('[') {
+        // An array of entries or a `[section]` header.
+        match rest.trim_start().chars().next() {
+            None | Some('{' | ']' | '"') => Some(FileFormat::Json),
+            Some(_) if rest.ends_with(']') => Some(FileFormat::Ini),
+            Some(_) => None,
+        }
+    } else if line == "---" || line.starts_with("- ") {
+        Some(FileFormat::Yaml)
+    } else if line.len() > 2 && line.starts_with('%') && line.ends_with('%') {
+        Some(FileFormat::Desc)
+    } else if line
+        .split_once('=')
+        .is_some_and(|(key, _)| is_key(key.trim()))
+    {
+        Some(FileFormat::Info)
+    } else {
+        None
+    }
+}
+
+/// Guess the format of a file from its first bytes, decompressing it if needed.
+pub fn sniff_file(path: &Path) -> io::Result<Option<FileFormat>> {
+    let mut head = Vec::new();
+    super::bulk::decompress(File::open(path)?)?
+        .take(SNIFF_LEN)
+        .read_to_end(&mut head)?;
+
+    // The head may end in the middle of a character.
+    if matches!(
+        Encoding::detect(&head),
+        Encoding::Utf16Le | Encoding::Utf16Be
+    ) {
+        head.truncate(head.len() & !1);
+    }
+    let content = match decode(head.clone()) {
+        Ok(content) => content,
+        Err(_) => String::from_utf8_lossy(&head).into_owned(),
+    };
+
+    Ok(sniff(&content))
+}
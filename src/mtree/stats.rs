@@ -0,0 +1,108 @@
+use std::{cmp::Reverse, collections::BTreeMap, fmt};
+
+use serde::Serialize;
+
+use super::{Entry, PathType, Timestamp};
+
+/// A summary of a manifest, e.g. to get an overview of a package.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Stats<'e> {
+    /// The number of entries.
+    pub entries: usize,
+    /// The number of entries per type. Entries without a type aren't counted.
+    pub types: BTreeMap<PathType, usize>,
+    /// The sum of the sizes of all files in bytes.
+    pub total_size: usize,
+    /// The biggest files with their size, the biggest first.
+    pub largest_files: Vec<(&'e str, usize)>,
+    /// The paths with the most components with their depth, the deepest first.
+    pub deepest_paths: Vec<(&'e str, usize)>,
+    /// The percentage of files that have at least one digest.
+    pub digest_coverage: f64,
+    /// The oldest and newest modification time.
+    pub time_range: Option<(Timestamp, Timestamp)>,
+}
+
+impl<'e> Stats<'e> {
+    /// The number of entries in [Stats::largest_files] and [Stats::deepest_paths].
+    pub const TOP: usize = 10;
+
+    /// Compute the statistics of the given entries.
+    pub fn from_entries<'a: 'e>(entries: impl IntoIterator<Item = &'e Entry<'a>>) -> Self {
+        let mut stats = Self::default();
+        let mut files = Vec::new();
+        let mut depths = Vec::new();
+        let mut digests = 0;
+
+        for entry in entries {
+            stats.entries += 1;
+            if let Some(path_type) = entry.path_type {
+                *stats.types.entry(path_type).or_default() += 1;
+            }
+            if let Some(time) = entry.time {
+                stats.time_range = Some(match stats.time_range {
+                    Some((oldest, newest)) => (oldest.min(time), newest.max(time)),
+                    None => (time, time),
+                });
+            }
+            depths.push((entry.path.as_ref(), depth(&entry.path)));
+
+            if matches!(entry.path_type, None | Some(PathType::File)) {
+                let size = entry.size.unwrap_or_default();
+                stats.total_size += size;
+                files.push((entry.path.as_ref(), size));
+                if entry.md5digest.is_some()
+                    || entry.sha1digest.is_some()
+                    || entry.sha256digest.is_some()
+                {
+                    digests += 1;
+                }
+            }
+        }
+
+        if !files.is_empty() {
+            stats.digest_coverage = digests as f64 * 100.0 / files.len() as f64;
+        }
+        // The sort is stable, so entries of the same size or depth stay in manifest order.
+        files.sort_by_key(|(_, size)| Reverse(*size));
+        files.truncate(Self::TOP);
+        stats.largest_files = files;
+        depths.sort_by_key(|(_, depth)| Reverse(*depth));
+        depths.truncate(Self::TOP);
+        stats.deepest_paths = depths;
+
+        stats
+    }
+}
+
+impl fmt::Display for Stats<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "entries: {}", self.entries)?;
+        for (path_type, count) in &self.types {
+            writeln!(f, "  {}: {count}", path_type.as_str())?;
+        }
+        writeln!(f, "total size: {} bytes", self.total_size)?;
+        writeln!(f, "digest coverage: {:.1}%", self.digest_coverage)?;
+        if let Some((oldest, newest)) = self.time_range {
+            writeln!(f, "time range: {oldest} - {newest}")?;
+        }
+        writeln!(f, "largest files:")?;
+        for (path, size) in &self.largest_files {
+            writeln!(f, "  {size} {path}")?;
+        }
+        writeln!(f, "deepest paths:")?;
+        for (path, depth) in &self.deepest_paths {
+            writeln!(f, "  {depth} {path}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The number of components of a path below the root, e.g. 3 for `./usr/bin/foo`.
+fn depth(path: &str) -> usize {
+    let path = path.strip_prefix('.').unwrap_or(path);
+    path.split('/')
+        .filter(|component| !component.is_empty())
+        .count()
+}
@@ -0,0 +1,149 @@
+use std::{
+    fmt,
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// A point in time as given by the `time=` keyword.
+///
+/// The value is stored as seconds since the epoch plus a fractional part with nanosecond
+/// precision (`time=1700000000.123456789`). Timestamps before 1970 have negative seconds, the
+/// nanoseconds always count forward from there, so `-1.25` is stored as `-2` seconds plus
+/// `750_000_000` nanoseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Timestamp {
+    pub secs: i64,
+    pub nanos: u32,
+}
+
+impl Timestamp {
+    pub fn new(secs: i64, nanos: u32) -> Self {
+        Self { secs, nanos }
+    }
+
+    pub fn to_system_time(self) -> SystemTime {
+        let nanos = Duration::from_nanos(self.nanos.into());
+        if self.secs >= 0 {
+            UNIX_EPOCH + Duration::from_secs(self.secs.unsigned_abs()) + nanos
+        } else {
+            UNIX_EPOCH - Duration::from_secs(self.secs.unsigned_abs()) + nanos
+        }
+    }
+
+    /// Format the timestamp as an RFC 3339 date in UTC without the fraction, e.g.
+    /// `2023-11-14T22:13:20Z`.
+    pub fn to_rfc3339(self) -> String {
+        let (days, secs) = (self.secs.div_euclid(86_400), self.secs.rem_euclid(86_400));
+        // The civil date of a day count, see http://howardhinnant.github.io/date_algorithms.html
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month + 2) / 5 + 1;
+        let month = if month < 10 { month + 3 } else { month - 9 };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+        format!(
+            "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+            secs / 3600,
+            secs / 60 % 60,
+            secs % 60
+        )
+    }
+}
+
+impl From<SystemTime> for Timestamp {
+    fn from(time: SystemTime) -> Self {
+        match time.duration_since(UNIX_EPOCH) {
+            Ok(after) => Self::new(after.as_secs() as i64, after.subsec_nanos()),
+            Err(err) => {
+                let before = err.duration();
+                let secs = -(before.as_secs() as i64);
+                match before.subsec_nanos() {
+                    0 => Self::new(secs, 0),
+                    nanos => Self::new(secs - 1, 1_000_000_000 - nanos),
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for Timestamp {
+    type Err = ParseTimestampError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negative, s) = match s.strip_prefix('-') {
+            Some(s) => (true, s),
+            None => (false, s),
+        };
+        let (secs, fraction) = s.split_once('.').unwrap_or((s, ""));
+        if secs.is_empty() || !secs.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(ParseTimestampError::InvalidSeconds);
+        }
+        let secs: i64 = secs
+            .parse()
+            .map_err(|_| ParseTimestampError::InvalidSeconds)?;
+        let nanos = parse_nanos(fraction)?;
+
+        Ok(match (negative, nanos) {
+            (false, _) => Self::new(secs, nanos),
+            (true, 0) => Self::new(-secs, 0),
+            (true, nanos) => Self::new(-secs - 1, 1_000_000_000 - nanos),
+        })
+    }
+}
+
+/// Convert the decimal places of a timestamp to nanoseconds.
+/// `5` is half a second, `000000001` is a single nanosecond.
+fn parse_nanos(fraction: &str) -> Result<u32, ParseTimestampError> {
+    if fraction.len() > 9 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(ParseTimestampError::InvalidFraction);
+    }
+
+    Ok(fraction
+        .bytes()
+        .chain(std::iter::repeat(b'0'))
+        .take(9)
+        .fold(0, |nanos, digit| nanos * 10 + u32::from(digit - b'0')))
+}
+
+impl fmt::Display for Timestamp {
+    /// Format the timestamp the same way libarchive does.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (sign, secs, nanos) = match (self.secs < 0, self.nanos) {
+            (false, nanos) => ("", self.secs.unsigned_abs(), nanos),
+            (true, 0) => ("-", self.secs.unsigned_abs(), 0),
+            (true, nanos) => ("-", self.secs.unsigned_abs() - 1, 1_000_000_000 - nanos),
+        };
+        if nanos == 0 {
+            write!(f, "{sign}{secs}.0")
+        } else {
+            write!(f, "{sign}{secs}.{nanos:09}")
+        }
+    }
+}
+
+/// Errors that can occur when parsing a [Timestamp].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseTimestampError {
+    InvalidSeconds,
+    InvalidFraction,
+}
+
+impl fmt::Display for ParseTimestampError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidSeconds => write!(f, "invalid seconds in timestamp"),
+            Self::InvalidFraction => {
+                write!(f, "timestamp fraction must be at most 9 decimal digits")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseTimestampError {}
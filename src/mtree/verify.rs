@@ -0,0 +1,497 @@
+use std::{
+    fmt,
+    fs::{self, Metadata},
+    io,
+    path::{Component, Path, PathBuf},
+};
+
+use serde::Serialize;
+
+#[cfg(feature = "digests")]
+use std::sync::Arc;
+
+use super::{DigestAlgorithm, Entry, Gid, Mode, PathType, Timestamp, Uid};
+#[cfg(feature = "digests")]
+use super::{Excludes, Progress};
+
+/// A difference between an entry of a manifest and the actual file on disk.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Mismatch {
+    /// The path of the entry as written in the manifest.
+    pub path: String,
+    #[serde(flatten)]
+    pub kind: MismatchKind,
+}
+
+/// What exactly differs between the manifest and the filesystem.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case", tag = "kind")]
+pub enum MismatchKind {
+    /// The path doesn't exist.
+    Missing,
+    /// The path exists, but isn't part of the manifest.
+    Extra,
+    /// The path couldn't be inspected, e.g. due to missing permissions.
+    Inaccessible {
+        error: String,
+    },
+    Type {
+        expected: PathType,
+        actual: PathType,
+    },
+    Size {
+        expected: usize,
+        actual: u64,
+    },
+    Mode {
+        expected: Mode,
+        actual: Mode,
+    },
+    Uid {
+        expected: Uid,
+        actual: Uid,
+    },
+    Gid {
+        expected: Gid,
+        actual: Gid,
+    },
+    Time {
+        expected: Timestamp,
+        actual: Timestamp,
+    },
+    Link {
+        expected: String,
+        actual: String,
+    },
+    Digest {
+        algorithm: DigestAlgorithm,
+        expected: String,
+        actual: String,
+    },
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.path)?;
+        match &self.kind {
+            MismatchKind::Missing => write!(f, "missing"),
+            MismatchKind::Extra => write!(f, "not in manifest"),
+            MismatchKind::Inaccessible { error } => write!(f, "inaccessible ({error})"),
+            MismatchKind::Type { expected, actual } => write!(
+                f,
+                "type mismatch (expected {}, found {})",
+                expected.as_str(),
+                actual.as_str()
+            ),
+            MismatchKind::Size { expected, actual } => {
+                write!(f, "size mismatch (expected {expected}, found {actual})")
+            }
+            MismatchKind::Mode { expected, actual } => {
+                write!(f, "mode mismatch (expected {expected}, found {actual})")
+            }
+            MismatchKind::Uid { expected, actual } => {
+                write!(f, "uid mismatch (expected {expected}, found {actual})")
+            }
+            MismatchKind::Gid { expected, actual } => {
+                write!(f, "gid mismatch (expected {expected}, found {actual})")
+            }
+            MismatchKind::Time { expected, actual } => {
+                write!(f, "time mismatch (expected {expected}, found {actual})")
+            }
+            MismatchKind::Link { expected, actual } => {
+                write!(f, "link mismatch (expected {expected}, found {actual})")
+            }
+            MismatchKind::Digest {
+                algorithm,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{algorithm} digest mismatch (expected {expected}, found {actual})"
+            ),
+        }
+    }
+}
+
+/// Check the given entries against the filesystem below `root`.
+///
+/// Each entry path is relative to `root`, so `./usr/bin/foo` is looked up at `<root>/usr/bin/foo`.
+/// Only properties that are set on an entry are checked.
+/// To skip some paths, pass the entries through [Excludes::filter](super::Excludes::filter).
+#[tracing::instrument(skip(entries), fields(root = %root.display()))]
+pub fn verify<'e, 'a: 'e>(
+    entries: impl IntoIterator<Item = &'e Entry<'a>>,
+    root: &Path,
+) -> Vec<Mismatch> {
+    let mismatches: Vec<_> = entries
+        .into_iter()
+        .flat_map(|entry| verify_entry(entry, root))
+        .collect();
+    tracing::info!(mismatches = mismatches.len(), "verified entries");
+    mismatches
+}
+
+/// Options for [verify_with_digests].
+#[cfg(feature = "digests")]
+#[derive(Debug, Clone)]
+pub struct DigestOptions {
+    /// The digests to recompute. Digests that aren't recorded for an entry are skipped.
+    pub algorithms: Vec<DigestAlgorithm>,
+    /// The number of threads to hash files with, `0` uses one thread per CPU.
+    pub threads: usize,
+    /// Paths that aren't verified at all.
+    pub excludes: Option<Excludes>,
+    /// Receives every verified entry with the size of the file.
+    pub progress: Option<Arc<dyn Progress>>,
+}
+
+#[cfg(feature = "digests")]
+impl Default for DigestOptions {
+    fn default() -> Self {
+        Self {
+            algorithms: DigestAlgorithm::ALL.to_vec(),
+            threads: 0,
+            excludes: None,
+            progress: None,
+        }
+    }
+}
+
+/// Like [verify], but additionally recompute the digests of all files and compare them with
+/// the recorded ones. Files are hashed in parallel on a dedicated thread pool.
+#[cfg(feature = "digests")]
+#[tracing::instrument(skip(entries, options), fields(root = %root.display(), algorithms = ?options.algorithms))]
+pub fn verify_with_digests(
+    entries: &[Entry],
+    root: &Path,
+    options: &DigestOptions,
+) -> Result<Vec<Mismatch>, rayon::ThreadPoolBuildError> {
+    use rayon::prelude::*;
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(options.threads)
+        .build()?;
+
+    let progress = options.progress.as_deref().unwrap_or(&());
+    let entries = entries
+        .iter()
+        .filter(|entry| {
+            options
+                .excludes
+                .as_ref()
+                .is_none_or(|excludes| !excludes.excludes(entry))
+        })
+        .collect::<Vec<_>>();
+    let size = |entry: &Entry| entry.size.unwrap_or(0) as u64;
+    tracing::info!(
+        entries = entries.len(),
+        threads = pool.current_num_threads(),
+        "verifying entries"
+    );
+    progress.start(entries.len() as u64, entries.iter().map(|e| size(e)).sum());
+
+    let mismatches: Vec<_> = pool.install(|| {
+        entries
+            .par_iter()
+            .flat_map_iter(|entry| {
+                let mismatches = verify_entry_with_digests(entry, root, &options.algorithms);
+                progress.advance(size(entry));
+                mismatches
+            })
+            .collect()
+    });
+    progress.finish();
+    tracing::info!(mismatches = mismatches.len(), "verified entries");
+
+    Ok(mismatches)
+}
+
+/// Like [verify_entry], but also recompute the digests of the file if it's where it's expected
+/// to be.
+#[cfg(feature = "digests")]
+pub(crate) fn verify_entry_with_digests(
+    entry: &Entry,
+    root: &Path,
+    algorithms: &[DigestAlgorithm],
+) -> Vec<Mismatch> {
+    let mut mismatches = verify_entry(entry, root);
+    let found = mismatches.iter().all(|mismatch| {
+        !matches!(
+            mismatch.kind,
+            MismatchKind::Missing | MismatchKind::Inaccessible { .. } | MismatchKind::Type { .. }
+        )
+    });
+    if found {
+        mismatches.extend(verify_digests(entry, root, algorithms));
+    }
+
+    mismatches
+}
+
+/// Recompute the digests of a file and compare them with the ones of the entry.
+#[cfg(feature = "digests")]
+fn verify_digests(entry: &Entry, root: &Path, algorithms: &[DigestAlgorithm]) -> Vec<Mismatch> {
+    let mismatch = |kind| Mismatch {
+        path: entry.path.to_string(),
+        kind,
+    };
+    let path = match fs_path(root, &entry.path) {
+        Ok(path) => path,
+        Err(err) => {
+            return vec![mismatch(MismatchKind::Inaccessible {
+                error: err.to_string(),
+            })]
+        }
+    };
+
+    let algorithms: Vec<_> = algorithms
+        .iter()
+        .copied()
+        .filter(|algorithm| algorithm.expected(entry).is_some())
+        .collect();
+    let is_file = fs::symlink_metadata(&path).is_ok_and(|metadata| metadata.is_file());
+    if algorithms.is_empty() || !is_file {
+        return Vec::new();
+    }
+
+    tracing::trace!(path = %path.display(), ?algorithms, "hashing file");
+    let digests = match super::digest::hash_file(&path, &algorithms) {
+        Ok(digests) => digests,
+        Err(err) => {
+            return vec![mismatch(MismatchKind::Inaccessible {
+                error: err.to_string(),
+            })]
+        }
+    };
+
+    compare_digests(entry, digests)
+        .into_iter()
+        .map(mismatch)
+        .collect()
+}
+
+/// Check a single entry against the filesystem below `root`.
+#[tracing::instrument(level = "trace", skip_all, fields(path = %entry.path))]
+pub fn verify_entry(entry: &Entry, root: &Path) -> Vec<Mismatch> {
+    let mismatch = |kind| Mismatch {
+        path: entry.path.to_string(),
+        kind,
+    };
+    let path = match fs_path(root, &entry.path) {
+        Ok(path) => path,
+        Err(err) => {
+            return vec![mismatch(MismatchKind::Inaccessible {
+                error: err.to_string(),
+            })]
+        }
+    };
+
+    let metadata = match fs::symlink_metadata(&path) {
+        Ok(metadata) => metadata,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            return vec![mismatch(MismatchKind::Missing)]
+        }
+        Err(err) => {
+            return vec![mismatch(MismatchKind::Inaccessible {
+                error: err.to_string(),
+            })]
+        }
+    };
+
+    let path_type = path_type(&metadata);
+    let link = match path_type {
+        PathType::Link => match fs::read_link(&path) {
+            Ok(link) => Some(link.to_string_lossy().into_owned()),
+            Err(err) => {
+                return vec![mismatch(MismatchKind::Inaccessible {
+                    error: err.to_string(),
+                })]
+            }
+        },
+        _ => None,
+    };
+
+    #[cfg(unix)]
+    let (mode, uid, gid) = {
+        use std::os::unix::fs::MetadataExt;
+        (
+            Some(Mode::from_bits_truncate(metadata.mode())),
+            Some(Uid(metadata.uid())),
+            Some(Gid(metadata.gid())),
+        )
+    };
+    #[cfg(not(unix))]
+    let (mode, uid, gid) = (None, None, None);
+
+    let found = Found {
+        path_type,
+        size: metadata.len(),
+        mode,
+        uid,
+        gid,
+        time: modified(&metadata),
+        link,
+    };
+
+    compare(entry, &found).into_iter().map(mismatch).collect()
+}
+
+/// The properties of a path as it was actually found, e.g. on disk or in an archive.
+/// Properties that can't be determined are `None` and never reported as a mismatch.
+pub(crate) struct Found {
+    pub path_type: PathType,
+    pub size: u64,
+    pub mode: Option<Mode>,
+    pub uid: Option<Uid>,
+    pub gid: Option<Gid>,
+    pub time: Timestamp,
+    pub link: Option<String>,
+}
+
+/// Compare an entry with what was actually found.
+pub(crate) fn compare(entry: &Entry, found: &Found) -> Vec<MismatchKind> {
+    if let Some(expected) = entry.path_type {
+        if expected != found.path_type {
+            // Nothing else can be compared in a meaningful way.
+            return vec![MismatchKind::Type {
+                expected,
+                actual: found.path_type,
+            }];
+        }
+    }
+
+    let mut kinds = Vec::new();
+    if let Some(expected) = entry.size {
+        if found.path_type == PathType::File && expected as u64 != found.size {
+            kinds.push(MismatchKind::Size {
+                expected,
+                actual: found.size,
+            });
+        }
+    }
+
+    if let Some(expected) = entry.time {
+        let actual = found.time;
+        // Most manifests don't record sub-second precision, so only compare seconds then.
+        let matches = match expected.nanos {
+            0 => expected.secs == actual.secs,
+            _ => expected == actual,
+        };
+        if !matches {
+            kinds.push(MismatchKind::Time { expected, actual });
+        }
+    }
+
+    if let Some(expected) = &entry.link {
+        let actual = found.link.as_deref().unwrap_or_default();
+        if expected != actual {
+            kinds.push(MismatchKind::Link {
+                expected: expected.to_string(),
+                actual: actual.to_string(),
+            });
+        }
+    }
+
+    // The permissions of symlinks are meaningless on most systems.
+    if let (Some(expected), Some(actual)) = (entry.mode, found.mode) {
+        if expected != actual && found.path_type != PathType::Link {
+            kinds.push(MismatchKind::Mode { expected, actual });
+        }
+    }
+    if let (Some(expected), Some(actual)) = (entry.uid, found.uid) {
+        if expected != actual {
+            kinds.push(MismatchKind::Uid { expected, actual });
+        }
+    }
+    if let (Some(expected), Some(actual)) = (entry.gid, found.gid) {
+        if expected != actual {
+            kinds.push(MismatchKind::Gid { expected, actual });
+        }
+    }
+
+    kinds
+}
+
+/// Compare recomputed digests with the ones recorded for an entry.
+#[cfg(feature = "digests")]
+pub(crate) fn compare_digests(
+    entry: &Entry,
+    digests: Vec<(DigestAlgorithm, String)>,
+) -> Vec<MismatchKind> {
+    digests
+        .into_iter()
+        .filter_map(|(algorithm, actual)| {
+            let expected = algorithm.expected(entry)?;
+            (!expected.eq_ignore_ascii_case(&actual)).then(|| MismatchKind::Digest {
+                algorithm,
+                expected: expected.to_string(),
+                actual,
+            })
+        })
+        .collect()
+}
+
+/// The location of a manifest path below the given root.
+///
+/// Manifests often come from untrusted downloads, so a path that would leave the root, with a
+/// `..` component or absolute after its leading `./`, is an [io::ErrorKind::InvalidInput]
+/// error instead of a file elsewhere.
+pub fn fs_path(root: &Path, path: &str) -> io::Result<PathBuf> {
+    let relative = match path {
+        "." => "",
+        path => path.strip_prefix("./").unwrap_or(path),
+    };
+    let inside = Path::new(relative)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if !inside {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{path} is outside of the root"),
+        ));
+    }
+
+    Ok(root.join(relative))
+}
+
+/// The type of a path on disk.
+fn path_type(metadata: &Metadata) -> PathType {
+    let file_type = metadata.file_type();
+    if file_type.is_symlink() {
+        return PathType::Link;
+    } else if file_type.is_dir() {
+        return PathType::Dir;
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        if file_type.is_block_device() {
+            return PathType::Block;
+        } else if file_type.is_char_device() {
+            return PathType::Char;
+        } else if file_type.is_fifo() {
+            return PathType::Fifo;
+        } else if file_type.is_socket() {
+            return PathType::Socket;
+        }
+    }
+
+    PathType::File
+}
+
+/// The modification time of a path on disk.
+fn modified(metadata: &Metadata) -> Timestamp {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        Timestamp::new(metadata.mtime(), metadata.mtime_nsec() as u32)
+    }
+    #[cfg(not(unix))]
+    {
+        metadata
+            .modified()
+            .map(Timestamp::from)
+            .unwrap_or(Timestamp::new(0, 0))
+    }
+}
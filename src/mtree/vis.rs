@@ -0,0 +1,100 @@
+use std::borrow::Cow;
+
+/// Decode a string in the vis(3) encoding, which manifests use for paths with whitespace or
+/// other special characters, e.g. `./foo\040bar` for `./foo bar`.
+///
+/// Octal escapes (`\040`), C-style escapes (`\n`, `\s`, `\\`, ..) and the meta and control
+/// forms (`\M-a`, `\^A`) are understood. Decoded bytes that aren't valid UTF-8 are replaced.
+/// The common case of a string without any escapes borrows from the input.
+pub fn decode(s: &str) -> Cow<'_, str> {
+    if !s.contains('\\') {
+        return Cow::Borrowed(s);
+    }
+
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut input = s.as_bytes();
+    while let Some((&byte, rest)) = input.split_first() {
+        input = rest;
+        if byte != b'\\' {
+            bytes.push(byte);
+            continue;
+        }
+        match unescape(input) {
+            Some((decoded, len)) => {
+                bytes.extend(decoded);
+                input = &input[len..];
+            }
+            // An invalid escape is kept as is.
+            None => bytes.push(b'\\'),
+        }
+    }
+
+    match String::from_utf8(bytes) {
+        Ok(decoded) => Cow::Owned(decoded),
+        Err(err) => Cow::Owned(String::from_utf8_lossy(err.as_bytes()).into_owned()),
+    }
+}
+
+/// Decode the escape sequence after a backslash.
+/// Returns the decoded byte (if any) and the number of bytes of the sequence.
+fn unescape(input: &[u8]) -> Option<(Option<u8>, usize)> {
+    let byte = |c: u8| Some((Some(c), 1));
+    match *input.first()? {
+        b'0'..=b'7' => {
+            let len = input
+                .iter()
+                .take(3)
+                .take_while(|c| (b'0'..=b'7').contains(c))
+                .count();
+            let value = input[..len]
+                .iter()
+                .fold(0u32, |value, c| value * 8 + u32::from(c - b'0'));
+            Some((Some(u8::try_from(value).ok()?), len))
+        }
+        b'\\' => byte(b'\\'),
+        b'a' => byte(0x07),
+        b'b' => byte(0x08),
+        b'f' => byte(0x0c),
+        b'n' => byte(b'\n'),
+        b'r' => byte(b'\r'),
+        b's' => byte(b' '),
+        b't' => byte(b'\t'),
+        b'v' => byte(0x0b),
+        b'E' => byte(0x1b),
+        // A hidden newline or `\$`, which both decode to nothing.
+        b'\n' | b'$' => Some((None, 1)),
+        b'M' => match input.get(1..3)? {
+            [b'-', c] => Some((Some(c | 0x80), 3)),
+            [b'^', b'?'] => Some((Some(0xff), 3)),
+            [b'^', c] => Some((Some((c & 0x1f) | 0x80), 3)),
+            _ => None,
+        },
+        b'^' => match *input.get(1)? {
+            b'?' => Some((Some(0x7f), 2)),
+            c => Some((Some(c & 0x1f), 2)),
+        },
+        _ => None,
+    }
+}
+
+/// Encode a string so it can be written to a manifest, the reverse of [decode].
+///
+/// Like libarchive, every byte that isn't a printable ASCII character, as well as `#` and `\`,
+/// is written as a 3-digit octal escape.
+pub fn encode(s: &str) -> Cow<'_, str> {
+    let safe = |byte: u8| byte.is_ascii_graphic() && byte != b'#' && byte != b'\\';
+    if s.bytes().all(safe) {
+        return Cow::Borrowed(s);
+    }
+
+    let mut encoded = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        if safe(byte) {
+            encoded.push(char::from(byte));
+        } else {
+            encoded.push_str(&format!("\\{byte:03o}"));
+        }
+    }
+
+    Cow::Owned(encoded)
+}
@@ -0,0 +1,39 @@
+use std::borrow::Cow;
+
+use super::{handrolled::Statements, Diag, Keyword, Property, Statement};
+
+/// Callbacks for the statements of a manifest, invoked by [visit] while scanning the input.
+///
+/// This is the streaming alternative to parsing the whole manifest into a `Vec<Statement>`,
+/// e.g. for consumers that only count or filter paths. The properties of a path are passed as
+/// written, it's up to the visitor to keep track of the `/set` defaults if it needs them.
+/// All methods do nothing by default.
+pub trait MtreeVisitor<'a> {
+    /// The `#mtree` header.
+    fn on_init(&mut self) {}
+
+    /// A `/set` command.
+    fn on_set(&mut self, _properties: Vec<Property<'a>>) {}
+
+    /// A `/unset` command, `all` is passed as every keyword.
+    fn on_unset(&mut self, _keywords: Vec<Keyword>) {}
+
+    /// A path line.
+    fn on_path(&mut self, _path: Cow<'a, str>, _properties: Vec<Property<'a>>) {}
+
+    /// A line that doesn't parse. Visiting continues with the next line.
+    fn on_error(&mut self, _error: Diag) {}
+}
+
+/// Scan a manifest and invoke the visitor for each statement, without collecting them.
+pub fn visit<'a, V: MtreeVisitor<'a> + ?Sized>(input: &'a str, visitor: &mut V) {
+    for statement in Statements::new(input) {
+        match statement {
+            Ok(Statement::Init) => visitor.on_init(),
+            Ok(Statement::Set(properties)) => visitor.on_set(properties),
+            Ok(Statement::Unset(keywords)) => visitor.on_unset(keywords),
+            Ok(Statement::Path { path, properties }) => visitor.on_path(path, properties),
+            Err(error) => visitor.on_error(error),
+        }
+    }
+}
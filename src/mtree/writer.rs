@@ -0,0 +1,68 @@
+use std::io::{self, Write};
+
+use super::{vis, Entry};
+
+/// Write entries as an mtree file, starting with the `#mtree` header.
+///
+/// Every entry is written as a single path line with all of its properties, no `/set` defaults
+/// are used.
+pub fn write<W: Write>(mut writer: W, entries: &[Entry]) -> io::Result<()> {
+    writeln!(writer, "#mtree")?;
+    for entry in entries {
+        write_entry(&mut writer, entry)?;
+    }
+
+    Ok(())
+}
+
+/// Write a single entry as a path line.
+/// Special characters in the path, the user and group names and the link target are vis-encoded.
+pub fn write_entry<W: Write>(mut writer: W, entry: &Entry) -> io::Result<()> {
+    write!(writer, "{}", vis::encode(&entry.path))?;
+    if let Some(path_type) = entry.path_type {
+        write!(writer, " type={}", path_type.as_str())?;
+    }
+    if let Some(uid) = entry.uid {
+        write!(writer, " uid={uid}")?;
+    }
+    if let Some(gid) = entry.gid {
+        write!(writer, " gid={gid}")?;
+    }
+    if let Some(uname) = &entry.uname {
+        write!(writer, " uname={}", vis::encode(uname))?;
+    }
+    if let Some(gname) = &entry.gname {
+        write!(writer, " gname={}", vis::encode(gname))?;
+    }
+    if let Some(mode) = entry.mode {
+        write!(writer, " mode={mode}")?;
+    }
+    if let Some(size) = entry.size {
+        write!(writer, " size={size}")?;
+    }
+    if let Some(nlink) = entry.nlink {
+        write!(writer, " nlink={nlink}")?;
+    }
+    if let Some(time) = entry.time {
+        write!(writer, " time={time}")?;
+    }
+    if let Some(link) = &entry.link {
+        write!(writer, " link={}", vis::encode(link))?;
+    }
+    if let Some(digest) = &entry.md5digest {
+        write!(writer, " md5digest={digest}")?;
+    }
+    if let Some(digest) = &entry.sha1digest {
+        write!(writer, " sha1digest={digest}")?;
+    }
+    if let Some(digest) = &entry.sha256digest {
+        write!(writer, " sha256digest={digest}")?;
+    }
+    if let Some(device) = entry.device {
+        write!(writer, " device={device}")?;
+    }
+    if let Some(device) = entry.resdevice {
+        write!(writer, " resdevice={device}")?;
+    }
+    writeln!(writer)
+}
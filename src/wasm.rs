@@ -0,0 +1,33 @@
+//! JavaScript bindings, e.g. for a web frontend that shows the contents of packages.
+//!
+//! Build them with
+//! `cargo rustc --lib --crate-type cdylib --release --target wasm32-unknown-unknown --features wasm`
+//! and generate the JavaScript glue with `wasm-bindgen --target web`.
+
+use serde::Serialize;
+use serde_wasm_bindgen::Serializer;
+use wasm_bindgen::prelude::*;
+
+use crate::mtree::{self, Backend, MtreeBackend};
+
+/// Parse a manifest into an array of its resolved entries, with the same fields as the JSON
+/// export. Throws an `Error` with the position if the manifest doesn't parse.
+#[wasm_bindgen(js_name = parseMtree)]
+pub fn parse_mtree(text: &str) -> Result<JsValue, JsError> {
+    let statements = Backend::default().parse(text.trim_end())?;
+    let entries = mtree::resolve(&statements);
+    to_js(&entries)
+}
+
+/// Parse a `.PKGINFO` or `.BUILDINFO` into an object that maps every key to the array of its
+/// values.
+#[wasm_bindgen(js_name = parsePkginfo)]
+pub fn parse_pkginfo(text: &str) -> Result<JsValue, JsError> {
+    let info = mtree::parse_info(text)?;
+    to_js(&info)
+}
+
+/// Convert a value to plain JavaScript objects, like `JSON.parse` would return them.
+fn to_js<T: Serialize>(value: &T) -> Result<JsValue, JsError> {
+    Ok(value.serialize(&Serializer::json_compatible())?)
+}
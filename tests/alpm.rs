@@ -0,0 +1,44 @@
+//! Convert package metadata and digests to the alpm-types newtypes.
+#![cfg(feature = "alpm-types")]
+
+use alpm_types::{Architecture, SystemArchitecture};
+use parser_tester::mtree::{parse_info, resolve, Backend, MtreeBackend, PackageInfo};
+
+#[test]
+fn package_info_from_pkginfo() {
+    let info = parse_info("pkgname = zbar\npkgver = 1:0.23-2\narch = x86_64\n").unwrap();
+    let package = PackageInfo::from_info(&info).unwrap();
+
+    assert_eq!(package.name.to_string(), "zbar");
+    assert_eq!(package.version.to_string(), "1:0.23-2");
+    assert_eq!(package.arch, Architecture::Some(SystemArchitecture::X86_64));
+}
+
+#[test]
+fn package_info_needs_a_valid_version() {
+    let info = parse_info("pkgname = zbar\npkgver = 0.23\narch = any\n").unwrap();
+    assert_eq!(
+        PackageInfo::from_info(&info).unwrap_err().code,
+        "invalid-field"
+    );
+
+    let info = parse_info("pkgname = zbar\narch = any\n").unwrap();
+    assert_eq!(
+        PackageInfo::from_info(&info).unwrap_err().code,
+        "missing-field"
+    );
+}
+
+#[test]
+fn checksums_of_entries() {
+    let sha256 = "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03";
+    let manifest =
+        format!("#mtree\n./a type=file sha256digest={sha256} md5digest=abcd\n./b type=dir");
+    let statements = Backend::default().parse(&manifest).unwrap();
+    let entries = resolve(&statements);
+
+    let checksum = entries[0].sha256_checksum().unwrap().unwrap();
+    assert_eq!(checksum.to_string(), sha256);
+    assert!(entries[0].md5_checksum().unwrap().is_err());
+    assert!(entries[1].sha256_checksum().is_none());
+}
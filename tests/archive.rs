@@ -0,0 +1,74 @@
+//! Verify manifests against tar archives that are built in memory.
+#![cfg(feature = "tar")]
+
+use parser_tester::mtree::{archive::verify_archive, resolve, Backend, MismatchKind, MtreeBackend};
+
+/// A package with a file and a hardlink to it, which has no data of its own.
+fn hardlinked() -> Vec<u8> {
+    let mut builder = tar::Builder::new(Vec::new());
+    let data = b"hello\n";
+    let mut file = member(tar::EntryType::Regular, data.len() as u64);
+    builder
+        .append_data(&mut file, "usr/bin/a", &data[..])
+        .unwrap();
+
+    let mut link = member(tar::EntryType::Link, 0);
+    builder
+        .append_link(&mut link, "usr/bin/b", "usr/bin/a")
+        .unwrap();
+
+    builder.into_inner().unwrap()
+}
+
+/// The header of a member that is owned by root.
+fn member(entry_type: tar::EntryType, size: u64) -> tar::Header {
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(entry_type);
+    header.set_size(size);
+    header.set_mode(0o755);
+    header.set_uid(0);
+    header.set_gid(0);
+    header.set_mtime(1700000000);
+    header
+}
+
+fn verify(manifest: &str, archive: &[u8]) -> Vec<(String, MismatchKind)> {
+    let statements = Backend::default().parse(manifest).unwrap();
+    verify_archive(&resolve(&statements), archive)
+        .unwrap()
+        .into_iter()
+        .map(|mismatch| (mismatch.path, mismatch.kind))
+        .collect()
+}
+
+#[test]
+fn hardlink_has_the_data_of_its_target() {
+    let sha256 = "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03";
+    let manifest = format!(
+        "#mtree\n\
+         /set type=file mode=755 time=1700000000.0\n\
+         ./usr/bin/a size=6 sha256digest={sha256}\n\
+         ./usr/bin/b size=6 sha256digest={sha256}"
+    );
+
+    assert_eq!(verify(&manifest, &hardlinked()), []);
+}
+
+#[test]
+fn hardlink_with_other_data() {
+    let manifest = "#mtree\n\
+         /set type=file mode=755 time=1700000000.0\n\
+         ./usr/bin/a size=6\n\
+         ./usr/bin/b size=7";
+
+    assert_eq!(
+        verify(manifest, &hardlinked()),
+        [(
+            "./usr/bin/b".to_string(),
+            MismatchKind::Size {
+                expected: 7,
+                actual: 6
+            }
+        )]
+    );
+}
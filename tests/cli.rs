@@ -0,0 +1,170 @@
+//! Run the command line tool and check that a manifest given as a file behaves the same as
+//! the manifest on stdin.
+
+use std::{
+    fs,
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// The exit code, stdout and stderr of the tool when run with `args`.
+fn run(args: &[&str], stdin: &str) -> (i32, String, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_parser-tester"))
+        .args(["--color", "never", "--no-progress"])
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    (
+        output.status.code().unwrap(),
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+/// Run `args` followed by the corpus file, and by `-` with the file on stdin, and check that
+/// both exit with `code` and print the same. Returns what they printed to stderr, with the path
+/// of the file replaced by `<stdin>`.
+fn file_and_stdin(args: &[&str], corpus: &str, code: i32) -> (String, String) {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/corpus/").to_string() + corpus;
+    let content = fs::read_to_string(&path).unwrap();
+
+    let (file_code, file_stdout, file_stderr) = run(&[args, &[path.as_str()]].concat(), "");
+    let (stdin_code, stdin_stdout, stdin_stderr) = run(&[args, &["-"]].concat(), &content);
+    assert_eq!((file_code, stdin_code), (code, code), "{args:?} {corpus}");
+    assert_eq!(file_stdout, stdin_stdout, "{args:?} {corpus}");
+
+    (file_stderr.replace(&path, "<stdin>"), stdin_stderr)
+}
+
+#[test]
+fn unknown_keyword_is_a_warning() {
+    for args in [&["parse"][..], &["--error-format", "json", "parse"]] {
+        let (file, stdin) = file_and_stdin(args, "unknown-keyword.mtree", 0);
+        assert_eq!(file, stdin);
+    }
+}
+
+#[test]
+fn duplicates_policy() {
+    for (policy, code) in [("error", 2), ("first-wins", 0), ("last-wins", 0)] {
+        let args = ["--duplicates", policy, "--format", "csv", "parse"];
+        let (file, stdin) = file_and_stdin(&args, "duplicate-path.mtree", code);
+        assert!(stdin.contains("duplicate path `./a`"), "{policy}: {stdin}");
+        assert_eq!(file, stdin, "{policy}");
+    }
+}
+
+#[test]
+fn json_errors() {
+    let args = ["--error-format", "json", "parse"];
+    let (file, stdin) = file_and_stdin(&args, "invalid-mode.mtree", 2);
+    assert_eq!(file, stdin);
+    for line in stdin.lines() {
+        let report: serde_json::Value = serde_json::from_str(line).unwrap();
+        assert_eq!(report["severity"], "error", "{line}");
+    }
+}
+
+#[test]
+fn get_with_duplicates_policy() {
+    let path = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/corpus/duplicate-path.mtree"
+    );
+    for (policy, mode) in [("first-wins", "644"), ("last-wins", "755")] {
+        for parser in ["chumsky", "nom", "handrolled"] {
+            let args = [
+                "--parser",
+                parser,
+                "--duplicates",
+                policy,
+                "--format",
+                "csv",
+            ];
+            let (code, stdout, _) = run(&[&args[..], &["get", path, "a"]].concat(), "");
+            assert_eq!(code, 0, "{policy} {parser}");
+            assert_eq!(stdout.lines().nth(1).unwrap().split(',').nth(6), Some(mode));
+        }
+    }
+    for parser in ["chumsky", "nom", "handrolled"] {
+        let args = [
+            "--parser",
+            parser,
+            "--duplicates",
+            "error",
+            "get",
+            path,
+            "a",
+        ];
+        assert_eq!(run(&args, "").0, 2, "{parser}");
+    }
+}
+
+#[test]
+fn get_dotfiles_and_the_root() {
+    let manifest = "#mtree\n. type=dir\n./.bashrc type=file\n./bashrc type=link link=x\n";
+    for (path, expected) in [
+        (".bashrc", "./.bashrc,file"),
+        ("./.bashrc", "./.bashrc,file"),
+        ("/.bashrc", "./.bashrc,file"),
+        ("bashrc", "./bashrc,link"),
+        (".", ".,dir"),
+        ("/", ".,dir"),
+    ] {
+        for parser in ["chumsky", "nom", "handrolled"] {
+            let args = [
+                "--parser",
+                parser,
+                "--format",
+                "csv",
+                "--columns",
+                "path,type",
+            ];
+            let (code, stdout, stderr) = run(&[&args[..], &["get", "-", path]].concat(), manifest);
+            assert_eq!(code, 0, "{path} {parser}: {stderr}");
+            assert_eq!(stdout.lines().nth(1), Some(expected), "{path} {parser}");
+        }
+    }
+}
+
+#[test]
+fn pacman_file_list_keeps_dotfiles() {
+    let manifest = "#mtree\n. type=dir\n./.PKGINFO type=file\n./.MTREE type=file\n\
+                    ./.INSTALL type=file\n./.bashrc type=file\n./usr type=dir\n";
+    let args = ["--format", "pacman", "--pkgname", "foo", "parse", "-"];
+    let (code, stdout, stderr) = run(&args, manifest);
+    assert_eq!(code, 0, "{stderr}");
+    assert_eq!(stdout, "foo /.bashrc\nfoo /usr/\n");
+}
+
+#[cfg(feature = "alpm-types")]
+#[test]
+fn vercmp() {
+    for (a, b, expected) in [
+        ("1.0-1", "1.0-2", "-1\n"),
+        ("1:0.1", "2.0", "1\n"),
+        ("1.0", "1.0", "0\n"),
+        ("1.0a", "1.0", "-1\n"),
+    ] {
+        assert_eq!(
+            run(&["vercmp", a, b], ""),
+            (0, expected.to_string(), String::new())
+        );
+    }
+
+    let (code, stdout, _) = run(&["vercmp", "--sort"], "1.0-2\n1:0.1-1\n0.9\n\n1.0rc1\n");
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "0.9\n1.0rc1\n1.0-2\n1:0.1-1\n");
+    assert_eq!(run(&["vercmp", "1.0", "a b"], "").0, 2);
+}
@@ -0,0 +1,176 @@
+//! Proptest strategies that generate structurally valid manifests.
+#![allow(dead_code)]
+
+use parser_tester::mtree::{vis, Device, Entry, Gid, Mode, PathType, Timestamp, Uid};
+use proptest::{option, prelude::*, sample::select};
+
+const PATH_TYPES: &[PathType] = &[
+    PathType::Block,
+    PathType::Char,
+    PathType::Dir,
+    PathType::Fifo,
+    PathType::File,
+    PathType::Link,
+    PathType::Socket,
+];
+
+const DEVICE_FORMATS: &[&str] = &[
+    "native", "386bsd", "4bsd", "bsdos", "freebsd", "hpux", "isc", "linux", "netbsd", "osf1",
+    "sco", "solaris", "sunos", "svr3", "svr4", "ultrix",
+];
+
+/// A path that may contain characters which have to be vis-encoded.
+pub fn path() -> impl Strategy<Value = String> {
+    r"\./[a-zA-Z0-9_+.# \\\té-]{1,12}(/[a-zA-Z0-9_+.# \\\té-]{1,12}){0,3}"
+}
+
+/// A symlink target, which may contain characters that have to be vis-encoded as well.
+pub fn link() -> impl Strategy<Value = String> {
+    r"[a-zA-Z0-9_./# \\\té-]{1,20}"
+}
+
+pub fn name() -> impl Strategy<Value = String> {
+    "[a-z_][a-z0-9_-]{0,8}"
+}
+
+pub fn mode() -> impl Strategy<Value = Mode> {
+    (0..=0o7777u32).prop_map(Mode::from_bits_truncate)
+}
+
+pub fn timestamp() -> impl Strategy<Value = Timestamp> {
+    (-100_000_000_000..100_000_000_000i64, 0..1_000_000_000u32)
+        .prop_map(|(secs, nanos)| Timestamp::new(secs, nanos))
+}
+
+pub fn device() -> impl Strategy<Value = Device> {
+    (select(DEVICE_FORMATS), any::<u32>(), any::<u32>()).prop_map(|(format, major, minor)| Device {
+        format: format.parse().unwrap(),
+        major,
+        minor,
+    })
+}
+
+pub fn hex(len: usize) -> impl Strategy<Value = String> {
+    proptest::string::string_regex(&format!("[0-9a-f]{{{len}}}")).unwrap()
+}
+
+/// An entry with a random subset of its properties. The type is always set, so that every
+/// entry has at least one property.
+pub fn entry() -> impl Strategy<Value = Entry<'static>> {
+    (
+        (
+            path(),
+            select(PATH_TYPES),
+            option::of(any::<u32>()),
+            option::of(any::<u32>()),
+            option::of(name()),
+            option::of(name()),
+            option::of(mode()),
+            option::of(any::<usize>()),
+        ),
+        (
+            option::of(1..100usize),
+            option::of(link()),
+            option::of(hex(32)),
+            option::of(hex(40)),
+            option::of(hex(64)),
+            option::of(timestamp()),
+            option::of(device()),
+            option::of(device()),
+        ),
+    )
+        .prop_map(
+            |(
+                (path, path_type, uid, gid, uname, gname, mode, size),
+                (nlink, link, md5, sha1, sha256, time, device, resdevice),
+            )| Entry {
+                path_type: Some(path_type),
+                uid: uid.map(Uid),
+                gid: gid.map(Gid),
+                uname: uname.map(Into::into),
+                gname: gname.map(Into::into),
+                mode,
+                size,
+                nlink,
+                link: link.map(Into::into),
+                md5digest: md5.map(Into::into),
+                sha1digest: sha1.map(Into::into),
+                sha256digest: sha256.map(Into::into),
+                time,
+                device,
+                resdevice,
+                ..Entry::new(path)
+            },
+        )
+}
+
+/// A single `keyword=value` property of a path or a `/set` line, including the short digest
+/// aliases.
+pub fn property() -> impl Strategy<Value = String> {
+    prop_oneof![
+        any::<u32>().prop_map(|uid| format!("uid={uid}")),
+        any::<u32>().prop_map(|gid| format!("gid={gid}")),
+        name().prop_map(|name| format!("uname={name}")),
+        name().prop_map(|name| format!("gname={name}")),
+        mode().prop_map(|mode| format!("mode={mode}")),
+        select(PATH_TYPES).prop_map(|t| format!("type={}", t.as_str())),
+        any::<usize>().prop_map(|size| format!("size={size}")),
+        (1..100usize).prop_map(|nlink| format!("nlink={nlink}")),
+        timestamp().prop_map(|time| format!("time={time}")),
+        link().prop_map(|link| format!("link={}", vis::encode(&link))),
+        (select(&["md5", "md5digest"][..]), hex(32)).prop_map(|(k, v)| format!("{k}={v}")),
+        (select(&["sha1", "sha1digest"][..]), hex(40)).prop_map(|(k, v)| format!("{k}={v}")),
+        (select(&["sha256", "sha256digest"][..]), hex(64)).prop_map(|(k, v)| format!("{k}={v}")),
+        device().prop_map(|device| format!("device={device}")),
+        device().prop_map(|device| format!("resdevice={device}")),
+    ]
+}
+
+/// A keyword of a `/unset` line, including the short digest aliases and `all`.
+pub fn keyword() -> impl Strategy<Value = &'static str> {
+    select(
+        &[
+            "uid",
+            "gid",
+            "uname",
+            "gname",
+            "mode",
+            "type",
+            "size",
+            "nlink",
+            "time",
+            "link",
+            "md5",
+            "md5digest",
+            "sha1",
+            "sha1digest",
+            "sha256",
+            "sha256digest",
+            "device",
+            "resdevice",
+            "all",
+        ][..],
+    )
+}
+
+/// A whole manifest with `/set` and `/unset` blocks and path lines that use all keywords.
+pub fn manifest() -> impl Strategy<Value = String> {
+    let line = prop_oneof![
+        1 => prop::collection::vec(property(), 1..4)
+            .prop_map(|properties| format!("/set {}", properties.join(" "))),
+        1 => prop::collection::vec(keyword(), 0..4)
+            .prop_map(|keywords| format!("/unset {}", keywords.join(" "))),
+        4 => (path(), prop::collection::vec(property(), 1..6)).prop_map(|(path, properties)| {
+            format!("{} {}", vis::encode(&path), properties.join(" "))
+        }),
+    ];
+
+    prop::collection::vec(line, 1..20).prop_map(|lines| {
+        let mut manifest = String::from("#mtree\n");
+        for line in lines {
+            manifest.push_str(&line);
+            manifest.push('\n');
+        }
+        manifest
+    })
+}
@@ -0,0 +1,84 @@
+//! Differential tests that run every parser backend over the same inputs and check that they
+//! all produce the same statements and accept or reject the same inputs.
+
+mod common;
+
+use std::fs;
+
+use parser_tester::mtree::{Backend, MtreeBackend};
+use proptest::prelude::*;
+
+/// Parse the input with all backends and compare the results with the ones of the first backend.
+fn check_backends(input: &str) -> Result<(), TestCaseError> {
+    let (reference, others) = Backend::ALL.split_first().unwrap();
+    let expected = reference.parse(input);
+    for backend in others {
+        let actual = backend.parse(input);
+        match (&expected, &actual) {
+            (Ok(expected), Ok(actual)) => prop_assert_eq!(
+                expected,
+                actual,
+                "{} and {} disagree on {:?}",
+                reference,
+                backend,
+                input
+            ),
+            (Err(_), Err(_)) => {}
+            _ => prop_assert!(
+                false,
+                "{} returned {:?}, but {} returned {:?} for {:?}",
+                reference,
+                expected,
+                backend,
+                actual,
+                input
+            ),
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn corpus() {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/corpus");
+    for file in fs::read_dir(dir).unwrap() {
+        let path = file.unwrap().path();
+        let input = fs::read_to_string(&path).unwrap();
+        for input in [input.as_str(), input.trim()] {
+            if let Err(err) = check_backends(input) {
+                panic!("{}: {err}", path.display());
+            }
+        }
+    }
+}
+
+/// A line that looks roughly like a manifest line, but is often invalid.
+fn line() -> impl Strategy<Value = String> {
+    prop_oneof![
+        Just(String::from("#mtree")),
+        "/(un)?set( (uid|gid|uname|gname|mode|type|size)=[0-9a-z]{0,5}){0,3}",
+        r"\.[a-z/.]{0,8}( (uid|gid|uname|mode|md5|sha1digest|sha256|size|nlink|time|type|link|device)=[-0-9a-fA-F.,xyz]{0,12}){0,4}",
+        "[ \t#/.=a-z0-9]{0,16}",
+    ]
+}
+
+proptest! {
+    #[test]
+    fn valid_manifests(manifest in common::manifest()) {
+        for backend in Backend::ALL {
+            prop_assert!(backend.parse(manifest.trim()).is_ok(), "{} rejected {:?}", backend, manifest);
+        }
+        check_backends(&manifest)?;
+    }
+
+    #[test]
+    fn random_lines(lines in prop::collection::vec(line(), 0..8), separator in "[ \t\r]{0,2}\n[ \t\n]{0,2}") {
+        check_backends(&lines.join(&separator))?;
+    }
+
+    #[test]
+    fn random_text(input in "[ \t\n#/.=,a-z0-9-]{0,64}") {
+        check_backends(&input)?;
+    }
+}
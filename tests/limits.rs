@@ -0,0 +1,56 @@
+//! Reject manifests over the limits with every parser, also when they are recovered line by
+//! line.
+
+use parser_tester::mtree::{Backend, Limits, ParseOptions};
+
+fn parse(backend: Backend, limits: Limits, manifest: &str) -> Result<usize, String> {
+    let options = ParseOptions {
+        backend,
+        limits,
+        ..ParseOptions::default()
+    };
+    match options.parse(manifest) {
+        Ok(parsed) => Ok(parsed.statements.len()),
+        Err(diagnostic) => Err(diagnostic.message),
+    }
+}
+
+#[test]
+fn too_many_statements() {
+    let limits = Limits {
+        max_statements: 2,
+        ..Limits::default()
+    };
+    // The unknown keyword makes the parsers fail, so the manifest is recovered line by line.
+    for manifest in ["#mtree\n./a\n./b\n", "#mtree\n./a bogus=1\n./b\n"] {
+        for backend in Backend::ALL {
+            assert_eq!(
+                parse(*backend, limits, manifest),
+                Err(String::from("manifest has more than 2 statements")),
+                "{backend} {manifest:?}"
+            );
+            assert_eq!(parse(*backend, Limits::default(), manifest), Ok(3));
+        }
+    }
+}
+
+#[test]
+fn too_many_properties() {
+    let limits = Limits {
+        max_properties: 2,
+        ..Limits::default()
+    };
+    for manifest in [
+        "#mtree\n./a type=file mode=644 uid=0\n",
+        "#mtree\n./a bogus=1 type=file mode=644 uid=0\n",
+        "#mtree\n/unset all\n",
+    ] {
+        for backend in Backend::ALL {
+            assert_eq!(
+                parse(*backend, limits, manifest),
+                Err(String::from("a statement has more than 2 properties")),
+                "{backend} {manifest:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,57 @@
+//! Check a small pacman database, written to a temporary directory, like `pacman -Qkk`.
+
+use std::{fs, path::Path};
+
+use parser_tester::mtree::localdb::{check, packages, CheckOptions};
+
+fn write(path: &Path, content: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+#[test]
+fn check_packages() {
+    let dir = std::env::temp_dir().join(format!("parser-tester-localdb-{}", std::process::id()));
+    let (db, root) = (dir.join("db"), dir.join("root"));
+    let zeros = "0".repeat(64);
+    write(
+        &db.join("local/app-1.0-1/desc"),
+        "%NAME%\napp\n\n%VERSION%\n1.0-1\n\n%BACKUP%\netc/app.conf\tabc\n\n",
+    );
+    write(
+        &db.join("local/app-1.0-1/mtree"),
+        &format!(
+            "#mtree\n/set type=file\n\
+             ./.PKGINFO size=1\n\
+             ./etc/app.conf size=3 sha256digest={zeros}\n\
+             ./usr/bin/app size=6 sha256digest={zeros}\n"
+        ),
+    );
+    // Without a version, the package can't be read, but the others are still checked.
+    write(&db.join("local/broken-1-1/desc"), "%NAME%\nbroken\n");
+    write(&root.join("etc/app.conf"), "changed\n");
+    write(&root.join("usr/bin/app"), "hello\n");
+
+    let mut packages = packages(&db).unwrap().into_iter();
+    let app = packages.next().unwrap().unwrap();
+    assert_eq!((app.name.as_str(), app.version.as_str()), ("app", "1.0-1"));
+    assert_eq!(app.backup, ["etc/app.conf"]);
+    let err = packages.next().unwrap().unwrap_err();
+    assert!(err.to_string().contains("has no %VERSION%"), "{err}");
+    assert!(packages.next().is_none());
+
+    let checks = check(&[app], &root, &CheckOptions::default()).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(checks[0].files, 2);
+    // The backup file was changed, which pacman doesn't report.
+    let paths = checks[0]
+        .mismatches
+        .iter()
+        .map(|mismatch| mismatch.path.as_str())
+        .collect::<Vec<_>>();
+    if cfg!(feature = "digests") {
+        assert_eq!(paths, ["./usr/bin/app"]);
+    } else {
+        assert_eq!(paths, [""; 0]);
+    }
+}
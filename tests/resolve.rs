@@ -0,0 +1,60 @@
+//! Resolve manifests with `/set` and `/unset` defaults into entries.
+
+use parser_tester::mtree::{resolve, Backend, Entry, Gid, Mode, MtreeBackend, PathType, Uid};
+
+fn resolve_with(backend: &Backend, manifest: &str) -> Vec<Entry<'static>> {
+    let statements = backend.parse(manifest).unwrap();
+    resolve(&statements)
+        .into_iter()
+        .map(Entry::into_owned)
+        .collect()
+}
+
+#[test]
+fn unset_removes_defaults() {
+    let manifest = "#mtree\n\
+         /set uid=5 gid=3 mode=644 type=file\n\
+         /unset uid mode\n\
+         ./a\n\
+         /unset all\n\
+         ./b type=dir";
+
+    for backend in Backend::ALL {
+        assert_eq!(
+            resolve_with(backend, manifest),
+            [
+                Entry {
+                    path_type: Some(PathType::File),
+                    gid: Some(Gid(3)),
+                    ..Entry::new("./a")
+                },
+                Entry {
+                    path_type: Some(PathType::Dir),
+                    ..Entry::new("./b")
+                },
+            ],
+            "{backend}"
+        );
+    }
+}
+
+#[test]
+fn path_overrides_defaults() {
+    let manifest = "#mtree\n\
+         /set uid=5 mode=644\n\
+         /unset uid\n\
+         /set uid=7\n\
+         ./a uid=1 mode=755";
+
+    for backend in Backend::ALL {
+        assert_eq!(
+            resolve_with(backend, manifest),
+            [Entry {
+                uid: Some(Uid(1)),
+                mode: Some(Mode::from_octal("755").unwrap()),
+                ..Entry::new("./a")
+            }],
+            "{backend}"
+        );
+    }
+}
@@ -0,0 +1,30 @@
+//! Write random entries as a manifest and check that every backend parses them back unchanged,
+//! and that every statement survives formatting and parsing it on its own.
+
+mod common;
+
+use parser_tester::mtree::{resolve, writer, Backend, MtreeBackend, Statement};
+use proptest::prelude::*;
+
+proptest! {
+    #[test]
+    fn write_then_parse(entries in prop::collection::vec(common::entry(), 1..20)) {
+        let mut manifest = Vec::new();
+        writer::write(&mut manifest, &entries).unwrap();
+        let manifest = String::from_utf8(manifest).unwrap();
+
+        for backend in Backend::ALL {
+            let statements = backend.parse(manifest.trim());
+            prop_assert!(statements.is_ok(), "{} failed on {:?}: {:?}", backend, manifest, statements);
+            prop_assert_eq!(&resolve(&statements.unwrap()), &entries, "{}", backend);
+        }
+    }
+
+    #[test]
+    fn display_then_parse(manifest in common::manifest()) {
+        for statement in Backend::default().parse(&manifest).unwrap() {
+            let line = statement.to_string();
+            prop_assert_eq!(Statement::try_from(line.as_str()), Ok(statement), "{}", line);
+        }
+    }
+}
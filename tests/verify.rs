@@ -0,0 +1,27 @@
+//! Verify manifests against a directory, written to a temporary directory.
+
+use std::fs;
+
+use parser_tester::mtree::{resolve, verify, Backend, MismatchKind, MtreeBackend};
+
+#[test]
+fn paths_outside_of_the_root() {
+    let dir = std::env::temp_dir().join(format!("parser-tester-verify-{}", std::process::id()));
+    let root = dir.join("root");
+    fs::create_dir_all(&root).unwrap();
+    // A file next to the root, which a manifest must not reach.
+    fs::write(dir.join("secret"), "secret\n").unwrap();
+
+    let manifest = "#mtree\n./../secret type=file\n.//secret type=file\n./../../etc type=dir";
+    let statements = Backend::default().parse(manifest).unwrap();
+    let mismatches = verify(&resolve(&statements), &root);
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(mismatches.len(), 3);
+    for mismatch in mismatches {
+        assert!(
+            matches!(&mismatch.kind, MismatchKind::Inaccessible { error } if error.contains("outside of the root")),
+            "{mismatch:?}"
+        );
+    }
+}